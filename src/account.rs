@@ -1,13 +1,15 @@
-use crate::{Mexc, PROD_API_URL};
-use reqwest::{StatusCode, Response};
-use anyhow::{anyhow, bail};
+use crate::{Mexc, MexcError, MexcResult, rate_limit::weights};
+use crate::orders::DEFAULT_RECV_WINDOW;
+use crate::utils::encode;
+use futures_util::future::join_all;
+use reqwest::{Method, Response};
 use serde::Deserialize;
-use crate::utils::get_timestamp;
 
-use crate::utils::parse_string_to_f64;
+use crate::num::{parse_number, Numeric};
 
 #[derive(Deserialize, Debug, Clone)]
-pub struct Account {
+#[serde(bound(deserialize = "N: Numeric"))]
+pub struct Account<N = f64> {
     #[serde(rename = "accountType")]
     pub account_type: String,
     #[serde(rename = "canDeposit")]
@@ -17,16 +19,62 @@ pub struct Account {
     #[serde(rename = "canWithdraw")]
     pub can_withdraw: bool,
     pub permissions: Vec<String>,
-    pub balances: Vec<AccountBalance>
+    pub balances: Vec<AccountBalance<N>>
 }
 
 #[derive(Deserialize, Debug, Clone)]
-pub struct AccountBalance {
+#[serde(bound(deserialize = "N: Numeric"))]
+pub struct AccountBalance<N = f64> {
     pub asset: String,
-    #[serde(deserialize_with = "parse_string_to_f64")]
-    pub free: f64,
-    #[serde(deserialize_with = "parse_string_to_f64")]
-    pub locked: f64
+    #[serde(deserialize_with = "parse_number")]
+    pub free: N,
+    #[serde(deserialize_with = "parse_number")]
+    pub locked: N
+}
+
+/// Our commission rates on a symbol, which may differ from the defaults in `SymbolInfo`
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct TradeFee {
+    #[serde(default)]
+    pub symbol: String,
+    #[serde(rename = "makerCommission", deserialize_with = "parse_number")]
+    pub maker_commission: f64,
+    #[serde(rename = "takerCommission", deserialize_with = "parse_number")]
+    pub taker_commission: f64
+}
+
+impl TradeFee {
+
+    /// Commission on a fill of `notional`
+    pub fn commission(&self, notional: f64, is_maker: bool) -> f64 {
+        let rate = if is_maker { self.maker_commission } else { self.taker_commission };
+        notional * rate
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct MxDeduct {
+    #[serde(rename = "mxDeductEnable")]
+    enabled: bool
+}
+
+// `{"data":..,"code":0,"msg":"success"}`, errors can come with a 200 status
+#[derive(Deserialize, Debug)]
+struct Envelope<T> {
+    data: Option<T>,
+    #[serde(default)]
+    code: i64,
+    #[serde(default)]
+    msg: String
+}
+
+impl<T> Envelope<T> {
+    fn into_data(self) -> MexcResult<T> {
+        match self.data {
+            Some(data) if self.code == 0 => Ok(data),
+            _ => Err(MexcError::Api { code: self.code, msg: self.msg })
+        }
+    }
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -37,70 +85,68 @@ pub struct ListenKeyReponse {
 
 impl Mexc {
 
-    pub async fn get_signed(&self, url: &str) -> anyhow::Result<Response> {
-        let api_key = self.api_key.as_ref().ok_or_else(|| anyhow!("Missing api key"))?;
-
-        let resp = self.client
-        .get(url)
-        .header("X-MEXC-APIKEY", api_key)
-        .send().await?;
-        Ok(resp)
+    pub async fn get_signed(&self, url: &str) -> MexcResult<Response> {
+        self.send_with_key(Method::GET, url).await
     }
 
-    pub async fn get_account(&self) -> anyhow::Result<Account> {
+    pub async fn get_account(&self) -> MexcResult<Account> {
+        self.get_account_as().await
+    }
 
-        let timestamp = get_timestamp();
+    /// `get_account` with balances read into `N`
+    pub async fn get_account_as<N: Numeric>(&self) -> MexcResult<Account<N>> {
+        self.send_signed(Method::GET, "/api/v3/account", "", weights::ACCOUNT).await
+    }
 
-        let order_request = format!("timestamp={timestamp}");
-        let signed_order = self.sign_request(order_request)?;
-        let url = format!("{PROD_API_URL}/api/v3/account?{signed_order}");
-        let resp: Response = self.get_signed(&url).await?;
+    /// Our maker and taker rates on `symbol`
+    pub async fn get_trade_fee(&self, symbol: &str, recv_window: Option<u64>) -> MexcResult<TradeFee> {
+        let recv_window = recv_window.unwrap_or(DEFAULT_RECV_WINDOW);
 
-        if resp.status() == StatusCode::OK {
-            let account: Account = resp.json().await?;
-            Ok(account)
-        } else {
-            let err = resp.text().await?;
-            bail!(err);
-        }
+        let request = format!("symbol={}&recvWindow={recv_window}", encode(symbol));
+        let resp: Envelope<TradeFee> = self.send_signed(Method::GET, "/api/v3/tradeFee", &request, weights::TRADE_FEE).await?;
+        let mut fee = resp.into_data()?;
+        fee.symbol = symbol.to_string();
+        Ok(fee)
     }
 
-    pub async fn get_listen_key(&self) -> anyhow::Result<String> {
-
-        let timestamp = get_timestamp();
-        let order_request = format!("timestamp={timestamp}");
-        let signed_order = self.sign_request(order_request)?;
+    /// `get_trade_fee` for each of `symbols`, requested concurrently
+    pub async fn get_trade_fees(&self, symbols: &[&str], recv_window: Option<u64>) -> MexcResult<Vec<TradeFee>> {
+        let requests = symbols.iter().map(|symbol| self.get_trade_fee(symbol, recv_window));
+        join_all(requests).await.into_iter().collect()
+    }
 
-        let url = format!("{PROD_API_URL}/api/v3/userDataStream?{signed_order}");
-        let resp: Response = self.post_signed(&url).await?;
+    /// Whether fees are paid in MX when the balance allows it
+    pub async fn get_mx_deduct(&self, recv_window: Option<u64>) -> MexcResult<bool> {
+        let recv_window = recv_window.unwrap_or(DEFAULT_RECV_WINDOW);
 
-        let keyresp: ListenKeyReponse = resp.json().await?;
-        Ok(keyresp.listen_key)
+        let request = format!("recvWindow={recv_window}");
+        let resp: Envelope<MxDeduct> = self.send_signed(Method::GET, "/api/v3/mxDeduct/enable", &request, weights::DEFAULT).await?;
+        Ok(resp.into_data()?.enabled)
     }
 
-    pub async fn keep_alive_listen_key(&self, listen_key: &str) -> anyhow::Result<String> {
+    /// Turns paying fees in MX on or off, returns the new status
+    pub async fn set_mx_deduct(&self, enable: bool, recv_window: Option<u64>) -> MexcResult<bool> {
+        let recv_window = recv_window.unwrap_or(DEFAULT_RECV_WINDOW);
 
-        let timestamp = get_timestamp();
-        let order_request = format!("listenKey={listen_key}&timestamp={timestamp}");
-        let signed_order = self.sign_request(order_request)?;
-
-        let url = format!("{PROD_API_URL}/api/v3/userDataStream?{signed_order}");
-        let resp: Response = self.put_signed(&url).await?;
+        let request = format!("mxDeductEnable={enable}&recvWindow={recv_window}");
+        let resp: Envelope<MxDeduct> = self.send_signed(Method::POST, "/api/v3/mxDeduct/enable", &request, weights::DEFAULT).await?;
+        Ok(resp.into_data()?.enabled)
+    }
 
-        let keyresp: ListenKeyReponse = resp.json().await?;
+    pub async fn get_listen_key(&self) -> MexcResult<String> {
+        let keyresp: ListenKeyReponse = self.send_signed(Method::POST, "/api/v3/userDataStream", "", weights::DEFAULT).await?;
         Ok(keyresp.listen_key)
     }
 
-    pub async fn delete_listen_key(&self, listen_key: &str) -> anyhow::Result<String> {
-
-        let timestamp = get_timestamp();
-        let order_request = format!("listenKey={listen_key}&timestamp={timestamp}");
-        let signed_order = self.sign_request(order_request)?;
-
-        let url = format!("{PROD_API_URL}/api/v3/userDataStream?{signed_order}");
-        let resp: Response = self.delete_signed(&url).await?;
+    pub async fn keep_alive_listen_key(&self, listen_key: &str) -> MexcResult<String> {
+        let order_request = format!("listenKey={listen_key}");
+        let keyresp: ListenKeyReponse = self.send_signed(Method::PUT, "/api/v3/userDataStream", &order_request, weights::DEFAULT).await?;
+        Ok(keyresp.listen_key)
+    }
 
-        let keyresp: ListenKeyReponse = resp.json().await?;
+    pub async fn delete_listen_key(&self, listen_key: &str) -> MexcResult<String> {
+        let order_request = format!("listenKey={listen_key}");
+        let keyresp: ListenKeyReponse = self.send_signed(Method::DELETE, "/api/v3/userDataStream", &order_request, weights::DEFAULT).await?;
         Ok(keyresp.listen_key)
     }
 }
@@ -111,6 +157,28 @@ mod tests {
 
     use super::*;
     use crate::utils::unlock_keys;
+    use crate::testing::serve;
+
+    #[tokio::test]
+    pub async fn test_trade_fees_and_mx_deduct() {
+        let (base_url, handle) = serve(vec![
+            (200, r#"{"data":{"makerCommission":0.000000000000000000,"takerCommission":"0.0005"},"code":0,"msg":"success","timestamp":1669109672717}"#),
+            (200, r#"{"data":{"mxDeductEnable":true},"code":0,"msg":"success","timestamp":1669109672717}"#),
+            (200, r#"{"data":null,"code":700004,"msg":"Mandatory parameter 'mxDeductEnable' was not sent","timestamp":1669109672717}"#)
+        ]).await;
+        let client = Mexc::builder().base_url(base_url).api_key("key").api_secret("secret").build().unwrap();
+
+        let fee = client.get_trade_fee("MXUSDT", None).await.unwrap();
+        assert_eq!(fee, TradeFee { symbol: "MXUSDT".into(), maker_commission: 0.0, taker_commission: 0.0005 });
+        assert_eq!(fee.commission(1000.0, false), 0.5);
+        assert!(client.set_mx_deduct(true, None).await.unwrap());
+        assert_eq!(client.get_mx_deduct(None).await.unwrap_err().code(), Some(700004));
+
+        let requests = handle.await.unwrap();
+        assert!(requests[0].starts_with("GET /api/v3/tradeFee?symbol=MXUSDT&"));
+        assert!(requests[1].starts_with("POST /api/v3/mxDeduct/enable?mxDeductEnable=true&"));
+        assert!(requests[2].starts_with("GET /api/v3/mxDeduct/enable?recvWindow="));
+    }
 
     #[tokio::test]
     pub async fn test_get_account() {
@@ -137,7 +205,7 @@ mod tests {
 
         let listen_key = "enter key here";
 
-        let key = client.keep_alive_listen_key(&listen_key).await.unwrap();
+        let key = client.keep_alive_listen_key(listen_key).await.unwrap();
         dbg!(key);
     }
 
@@ -148,7 +216,7 @@ mod tests {
 
         let listen_key = "enter key here";
 
-        let key = client.delete_listen_key(&listen_key).await.unwrap();
+        let key = client.delete_listen_key(listen_key).await.unwrap();
         dbg!(key);
     }
 }
\ No newline at end of file
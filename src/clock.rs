@@ -0,0 +1,96 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::time::Duration;
+use crate::utils::get_timestamp;
+
+/// Number of server time samples per sync, the one with the shortest round trip wins
+pub const CLOCK_SYNC_SAMPLES: usize = 3;
+pub const DEFAULT_CLOCK_SYNC_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Offset between the local clock and the exchange clock, used for every signed timestamp.
+/// Clones share the same offset, so one sync task can serve several clients.
+#[derive(Clone, Default, Debug)]
+pub struct ServerClock {
+    offset_ms: Arc<AtomicI64>
+}
+
+impl ServerClock {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Milliseconds to add to the local clock to get the server clock
+    pub fn offset(&self) -> i64 {
+        self.offset_ms.load(Ordering::Relaxed)
+    }
+
+    pub fn set_offset(&self, offset_ms: i64) {
+        self.offset_ms.store(offset_ms, Ordering::Relaxed);
+    }
+
+    /// Current server time estimate in milliseconds
+    pub fn timestamp(&self) -> u128 {
+        (get_timestamp() as i128 + self.offset() as i128).max(0) as u128
+    }
+
+    /// Offset implied by a server time read between the local `sent` and `received` timestamps,
+    /// assuming the server stamped it halfway through the round trip
+    pub fn offset_from_sample(sent: u128, server_time: u128, received: u128) -> i64 {
+        let midpoint = (sent + received) / 2;
+        (server_time as i128 - midpoint as i128) as i64
+    }
+
+    /// Measures `samples` round trips with `server_time` and keeps the offset of the fastest one
+    pub(crate) async fn sync<F, Fut, E>(&self, samples: usize, mut server_time: F) -> Result<i64, E>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<u128, E>>
+    {
+        let mut best: Option<(u128, i64)> = None;
+
+        for _ in 0..samples.max(1) {
+            let sent = get_timestamp();
+            let server = server_time().await?;
+            let received = get_timestamp();
+
+            let rtt = received.saturating_sub(sent);
+            let better = match best {
+                Some((best_rtt, _)) => rtt < best_rtt,
+                None => true
+            };
+            if better {
+                best = Some((rtt, Self::offset_from_sample(sent, server, received)));
+            }
+        }
+
+        let offset = best.map(|(_, offset)| offset).unwrap_or_default();
+        self.set_offset(offset);
+        Ok(offset)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_offset_from_sample() {
+        assert_eq!(ServerClock::offset_from_sample(1000, 1300, 1200), 200);
+        assert_eq!(ServerClock::offset_from_sample(1000, 900, 1200), -200);
+    }
+
+    #[tokio::test]
+    pub async fn test_sync_applies_offset() {
+        let clock = ServerClock::new();
+        let shared = clock.clone();
+
+        let offset = clock.sync::<_, _, ()>(CLOCK_SYNC_SAMPLES, || async { Ok(get_timestamp() - 5000) }).await.unwrap();
+        assert!((-5010..=-4990).contains(&offset));
+        assert_eq!(shared.offset(), offset);
+
+        let drift = get_timestamp() as i128 - shared.timestamp() as i128;
+        assert!((4990..=5010).contains(&drift));
+    }
+}
@@ -0,0 +1,145 @@
+use reqwest::{Response, StatusCode};
+use reqwest::header::InvalidHeaderValue;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+
+pub type MexcResult<T> = Result<T, MexcError>;
+
+// https://mexcdevelop.github.io/apidocs/spot_v3_en/#error-code
+const SPOT_INSUFFICIENT_BALANCE: [i64; 2] = [10101, 30004];
+const SPOT_RATE_LIMITED: [i64; 2] = [429, 510];
+const SPOT_ORDER_NOT_FOUND: [i64; 2] = [-2011, -2013];
+
+// https://mexcdevelop.github.io/apidocs/contract_v1_en/#error-code-example
+const FUTURES_INSUFFICIENT_BALANCE: [i64; 1] = [2005];
+const FUTURES_RATE_LIMITED: [i64; 1] = [510];
+
+#[derive(thiserror::Error, Debug)]
+pub enum MexcError {
+    #[error("transport error: {0}")]
+    Transport(#[from] reqwest::Error),
+
+    #[error("http status {status}: {body}")]
+    Http { status: StatusCode, body: String },
+
+    #[error("mexc api error {code}: {msg}")]
+    Api { code: i64, msg: String },
+
+    #[error("mexc futures err resp {code}: {message:?}")]
+    FuturesResponse { code: i64, message: Option<String> },
+
+    #[error("failed to decode response: {source} on {body}")]
+    Decode { source: serde_json::Error, body: String },
+
+    #[error("missing credentials: {0}")]
+    MissingCredentials(&'static str),
+
+    #[error("invalid header value: {0}")]
+    InvalidHeader(#[from] InvalidHeaderValue),
+
+    #[error("invalid request: {0}")]
+    InvalidRequest(String),
+
+    #[error("websocket error: {0}")]
+    WebSocket(Box<tokio_tungstenite::tungstenite::Error>),
+
+    #[error("websocket connection closed")]
+    ConnectionClosed
+}
+
+impl From<tokio_tungstenite::tungstenite::Error> for MexcError {
+    fn from(err: tokio_tungstenite::tungstenite::Error) -> Self {
+        MexcError::WebSocket(Box::new(err))
+    }
+}
+
+// lets `OrderRequest`s go where an `Order` would need a fallible conversion
+impl From<std::convert::Infallible> for MexcError {
+    fn from(never: std::convert::Infallible) -> Self {
+        match never {}
+    }
+}
+
+#[derive(Deserialize, Debug)]
+struct ApiErrorBody {
+    code: i64,
+    msg: String
+}
+
+impl MexcError {
+
+    /// Error code reported by the exchange, if the error came from a decoded error body
+    pub fn code(&self) -> Option<i64> {
+        match self {
+            MexcError::Api { code, .. } => Some(*code),
+            MexcError::FuturesResponse { code, .. } => Some(*code),
+            _ => None
+        }
+    }
+
+    pub fn is_rate_limited(&self) -> bool {
+        match self {
+            MexcError::Http { status, .. } => *status == StatusCode::TOO_MANY_REQUESTS,
+            MexcError::Api { code, .. } => SPOT_RATE_LIMITED.contains(code),
+            MexcError::FuturesResponse { code, .. } => FUTURES_RATE_LIMITED.contains(code),
+            _ => false
+        }
+    }
+
+    pub fn is_insufficient_balance(&self) -> bool {
+        match self {
+            MexcError::Api { code, .. } => SPOT_INSUFFICIENT_BALANCE.contains(code),
+            MexcError::FuturesResponse { code, .. } => FUTURES_INSUFFICIENT_BALANCE.contains(code),
+            _ => false
+        }
+    }
+
+    pub fn is_order_not_found(&self) -> bool {
+        match self {
+            MexcError::Api { code, .. } => SPOT_ORDER_NOT_FOUND.contains(code),
+            _ => false
+        }
+    }
+
+    pub(crate) fn decode(source: serde_json::Error, body: String) -> Self {
+        MexcError::Decode { source, body }
+    }
+}
+
+/// Decodes a spot api response, mapping non-200 replies to `Api` when the body carries `{code, msg}`
+pub(crate) async fn decode_response<T: DeserializeOwned>(resp: Response) -> MexcResult<T> {
+    let status = resp.status();
+    let body = resp.text().await?;
+
+    if status == StatusCode::OK {
+        serde_json::from_str(&body).map_err(|source| MexcError::decode(source, body))
+    } else {
+        match serde_json::from_str::<ApiErrorBody>(&body) {
+            Ok(err) => Err(MexcError::Api { code: err.code, msg: err.msg }),
+            Err(_) => Err(MexcError::Http { status, body })
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_error_classification() {
+        let err = MexcError::Api { code: 10101, msg: "Insufficient balance".into() };
+        assert!(err.is_insufficient_balance());
+        assert!(!err.is_rate_limited());
+        assert_eq!(err.code(), Some(10101));
+
+        let err = MexcError::Http { status: StatusCode::TOO_MANY_REQUESTS, body: String::new() };
+        assert!(err.is_rate_limited());
+
+        let err = MexcError::Api { code: -2013, msg: "Order does not exist.".into() };
+        assert!(err.is_order_not_found());
+
+        let err = MexcError::FuturesResponse { code: 510, message: Some("Requests are too frequent".into()) };
+        assert!(err.is_rate_limited());
+    }
+}
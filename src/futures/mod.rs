@@ -1,30 +1,146 @@
 pub mod structures;
+pub mod ws;
 
-use serde_json::json;
-use anyhow::Context;
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
 use hmac::{Hmac, Mac};
 use md5::{Digest, Md5};
 use reqwest::Response;
 use sha2::Sha256;
 use reqwest::Client;
+use std::sync::Arc;
 use std::time::Duration;
 use std::time::Instant;
-use anyhow::{anyhow, bail};
+use tokio::task::JoinHandle;
 use reqwest::header::{HeaderMap, HeaderValue};
-use crate::utils::get_timestamp;
+use crate::clock::{ServerClock, CLOCK_SYNC_SAMPLES};
+use crate::{MexcError, MexcResult};
+use crate::num::Numeric;
+use crate::rate_limit::RateLimits;
+use crate::retry::RetryPolicy;
 
 
 use structures::*;
 
 pub const FUTURES_API_URL: &str = "https://contract.mexc.com";
+pub const FUTURES_WEB_URL: &str = "https://futures.mexc.com";
 
 pub struct MexcFutures {
     pub api_key: Option<String>,
     pub api_secret: Option<String>,
     pub web_user_token: Option<String>,
+    pub contract_url: String,
+    pub web_url: String,
+    pub rate_limits: RateLimits,
+    pub retry_policy: RetryPolicy,
+    pub clock: ServerClock,
     pub client: Client
 }
 
+pub struct MexcFuturesBuilder {
+    api_key: Option<String>,
+    api_secret: Option<String>,
+    web_user_token: Option<String>,
+    proxy_url: Option<String>,
+    contract_url: String,
+    web_url: String,
+    rate_limits: RateLimits,
+    retry_policy: RetryPolicy,
+    clock: ServerClock
+}
+
+impl Default for MexcFuturesBuilder {
+    fn default() -> Self {
+        Self {
+            api_key: None,
+            api_secret: None,
+            web_user_token: None,
+            proxy_url: None,
+            contract_url: FUTURES_API_URL.to_string(),
+            web_url: FUTURES_WEB_URL.to_string(),
+            rate_limits: RateLimits::futures(),
+            retry_policy: RetryPolicy::default(),
+            clock: ServerClock::new()
+        }
+    }
+}
+
+impl MexcFuturesBuilder {
+
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    pub fn api_secret(mut self, api_secret: impl Into<String>) -> Self {
+        self.api_secret = Some(api_secret.into());
+        self
+    }
+
+    pub fn web_user_token(mut self, web_user_token: impl Into<String>) -> Self {
+        self.web_user_token = Some(web_user_token.into());
+        self
+    }
+
+    pub fn proxy_url(mut self, proxy_url: impl Into<String>) -> Self {
+        self.proxy_url = Some(proxy_url.into());
+        self
+    }
+
+    /// Host for the contract api (market data, account, positions)
+    pub fn contract_url(mut self, contract_url: impl Into<String>) -> Self {
+        self.contract_url = contract_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Host for the web endpoints authenticated with the web user token (order placement)
+    pub fn web_url(mut self, web_url: impl Into<String>) -> Self {
+        self.web_url = web_url.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Budgets requests are throttled against; pass clones of the same limits to share them between clients
+    pub fn rate_limits(mut self, rate_limits: RateLimits) -> Self {
+        self.rate_limits = rate_limits;
+        self
+    }
+
+    /// Applied to the contract api reads, order placement is never retried
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Clock whose offset is applied to signed timestamps, share it to sync several clients at once
+    pub fn clock(mut self, clock: ServerClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn build(self) -> MexcResult<MexcFutures> {
+
+        let client = match self.proxy_url {
+            Some(url) => {
+                let proxy = reqwest::Proxy::all(url)?;
+                reqwest::Client::builder().proxy(proxy).build()?
+            },
+            None => reqwest::Client::new()
+        };
+
+        Ok(MexcFutures {
+            api_key: self.api_key,
+            api_secret: self.api_secret,
+            web_user_token: self.web_user_token,
+            contract_url: self.contract_url,
+            web_url: self.web_url,
+            rate_limits: self.rate_limits,
+            retry_policy: self.retry_policy,
+            clock: self.clock,
+            client
+        })
+    }
+}
+
 #[repr(u64)]
 pub enum OrderDirection {
     OpenLong = 1,
@@ -57,30 +173,25 @@ fn get_md5(string: &str) -> String {
 
 impl MexcFutures {
 
-    pub fn new(api_key: Option<String>, api_secret: Option<String>, web_user_token: Option<String>, proxy_url: Option<String>) -> anyhow::Result<Self> {
-
-        let client = match proxy_url {
-            Some(url) => {
-                let proxy = reqwest::Proxy::all(url)?;
-                reqwest::Client::builder().proxy(proxy).build()?
-            },
-            None => reqwest::Client::new()
-        };
-
-
-        Ok(Self {
+    pub fn new(api_key: Option<String>, api_secret: Option<String>, web_user_token: Option<String>, proxy_url: Option<String>) -> MexcResult<Self> {
+        MexcFuturesBuilder {
             api_key,
             api_secret,
             web_user_token,
-            client
-        })
+            proxy_url,
+            ..Default::default()
+        }.build()
+    }
+
+    pub fn builder() -> MexcFuturesBuilder {
+        MexcFuturesBuilder::default()
     }
 
-    pub fn sign_v1(&self, timestamp: u128, sign_params: Option<&str>) -> anyhow::Result<String> {
+    pub fn sign_v1(&self, timestamp: u128, sign_params: Option<&str>) -> MexcResult<String> {
 
 
-        let api_key = self.api_key.as_ref().ok_or_else(|| anyhow!("Missing api key"))?;
-        let secret_key = self.api_secret.as_ref().ok_or_else(|| anyhow!("Missing secret key"))?;
+        let api_key = self.api_key.as_ref().ok_or(MexcError::MissingCredentials("api key"))?;
+        let secret_key = self.api_secret.as_ref().ok_or(MexcError::MissingCredentials("secret key"))?;
 
 
         let sign = match sign_params {
@@ -95,8 +206,10 @@ impl MexcFutures {
         Ok(hex::encode(result.into_bytes()))
     }
 
-    pub async fn ping(&self) -> anyhow::Result<Duration> {
-        let url = format!("{FUTURES_API_URL}/api/v1/contract/ping");
+    pub async fn ping(&self) -> MexcResult<Duration> {
+        let url = format!("{}/api/v1/contract/ping", self.contract_url);
+
+        self.rate_limits.weight.acquire(1).await;
 
         let inst = Instant::now();
         let _ = self.client.get(url).send().await?;
@@ -104,35 +217,38 @@ impl MexcFutures {
         Ok(inst.elapsed())
     }
 
-    pub async fn get_futures_account(&self) -> anyhow::Result<Vec<FuturesBalance>> {
-
-        let url = format!("{}/api/v1/private/account/assets", FUTURES_API_URL);
-
-        let headers = self.generate_signed_header()?;
-
-        let resp: Response = self.client.get(url).headers(headers).send().await?;
-
-        let json_str: String = resp.text().await?;
-
-        //println!("{json_str}");
-
-        let resp: FuturesResponse = serde_json::from_str(&json_str)?;
-
-        //dbg!(&resp);
+    pub async fn get_server_time(&self) -> MexcResult<u128> {
+        self.get_public("/api/v1/contract/ping").await
+    }
 
-        if !resp.success {
-            bail!("mexc futures err resp: {:?}", resp.message);
-        }
+    /// Measures the offset to the server clock and applies it to all signed requests
+    pub async fn sync_clock(&self) -> MexcResult<i64> {
+        self.clock.sync(CLOCK_SYNC_SAMPLES, || self.get_server_time()).await
+    }
 
-        let balances: Vec<FuturesBalance> = serde_json::from_value(resp.data.context("Expected data field")?)?;
+    /// Re-syncs the clock every `interval`; a failed sync keeps the last known offset
+    pub fn spawn_clock_sync(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let _ = self.sync_clock().await;
+            }
+        })
+    }
 
-        Ok(balances)
+    pub async fn get_futures_account(&self) -> MexcResult<Vec<FuturesBalance>> {
+        self.get_futures_account_as().await
+    }
 
+    /// `get_futures_account` with balances read into `N`
+    pub async fn get_futures_account_as<N: Numeric>(&self) -> MexcResult<Vec<FuturesBalance<N>>> {
+        self.get_private("/api/v1/private/account/assets").await
     }
 
-    fn generate_signed_header(&self) -> anyhow::Result<HeaderMap> {
-        let api_key = self.api_key.as_ref().ok_or_else(|| anyhow!("Missing api key"))?;
-        let timestamp = get_timestamp();
+    fn generate_signed_header(&self) -> MexcResult<HeaderMap> {
+        let api_key = self.api_key.as_ref().ok_or(MexcError::MissingCredentials("api key"))?;
+        let timestamp = self.clock.timestamp();
         let signature = self.sign_v1(timestamp, None)?;
         let request_time = timestamp.to_string();
 
@@ -143,31 +259,37 @@ impl MexcFutures {
         headers.insert("Content-Type", HeaderValue::from_static("application/json"));
         Ok(headers)
     }
-    
-    pub async fn get_account_asset(&self, asset: &str) -> anyhow::Result<FuturesBalance> {
-
-        let path = format!("/api/v1/private/account/asset/{}", asset);
-        let url = format!("{}{}", FUTURES_API_URL, path);
-
-        let headers = self.generate_signed_header()?;
-
-        let resp: Response = self.client.get(url).headers(headers).send().await?;
-
-        let json_str: String = resp.text().await?;
 
-        //println!("{json_str}");
+    async fn get_private<T: DeserializeOwned>(&self, path: &str) -> MexcResult<T> {
+        let url = format!("{}{}", self.contract_url, path);
 
-        let resp: FuturesResponse = serde_json::from_str(&json_str)?;
+        // headers are generated per attempt so every retry is signed with a fresh timestamp
+        self.retry_policy.run(true, || async {
+            self.rate_limits.weight.acquire(1).await;
+            let headers = self.generate_signed_header()?;
 
-        //dbg!(&resp);
+            let resp: Response = self.client.get(&url).headers(headers).send().await?;
+            decode_futures_response(resp).await
+        }).await
+    }
 
-        if !resp.success {
-            bail!("mexc futures err resp: {:?}", resp.message);
-        }
+    async fn get_public<T: DeserializeOwned>(&self, path: &str) -> MexcResult<T> {
+        let url = format!("{}{}", self.contract_url, path);
 
-        let balance: FuturesBalance = serde_json::from_value(resp.data.context("Expected data field")?)?;
+        self.retry_policy.run(true, || async {
+            self.rate_limits.weight.acquire(1).await;
+            let resp: Response = self.client.get(&url).send().await?;
+            decode_futures_response(resp).await
+        }).await
+    }
+    
+    pub async fn get_account_asset(&self, asset: &str) -> MexcResult<FuturesBalance> {
+        self.get_account_asset_as(asset).await
+    }
 
-        Ok(balance)
+    pub async fn get_account_asset_as<N: Numeric>(&self, asset: &str) -> MexcResult<FuturesBalance<N>> {
+        let path = format!("/api/v1/private/account/asset/{}", asset);
+        self.get_private(&path).await
     }
 
     /*
@@ -175,12 +297,13 @@ impl MexcFutures {
     Use field userToken as web user token from: https://www.mexc.com/ucenter/api/user_info
     
      */
-    pub async fn submit_order(&self, symbol: &str, contract_units: u64, price: Option<f64>,leverage: u64, side: OrderDirection, open_type: OpenType, order_type: OrderType) -> anyhow::Result<OrderReceipt> {
+    #[allow(clippy::too_many_arguments)]
+    pub async fn submit_order(&self, symbol: &str, contract_units: u64, price: Option<f64>,leverage: u64, side: OrderDirection, open_type: OpenType, order_type: OrderType) -> MexcResult<OrderReceipt> {
 
 
-        let web_user_token = self.web_user_token.as_ref().ok_or_else(|| anyhow!("Missing web user token"))?;
+        let web_user_token = self.web_user_token.as_ref().ok_or(MexcError::MissingCredentials("web user token"))?;
 
-        let url = "https://futures.mexc.com/api/v1/private/order/create";
+        let url = format!("{}/api/v1/private/order/create", self.web_url);
 
         let mut params = json!({
             "symbol": symbol,
@@ -199,7 +322,10 @@ impl MexcFutures {
         }
 
 
-        let timestamp = get_timestamp().to_string();
+        self.rate_limits.orders.acquire(1).await;
+        self.rate_limits.weight.acquire(1).await;
+
+        let timestamp = self.clock.timestamp().to_string();
 
         let partial_hash =  {
             let concat = format!("{web_user_token}{timestamp}");
@@ -221,77 +347,39 @@ impl MexcFutures {
         headers.insert("authorization", HeaderValue::from_str(web_user_token)?);
         headers.insert("user-agent", HeaderValue::from_static("MEXC/7 CFNetwork/1474 Darwin/23.0.0"));
         headers.insert("content-type", HeaderValue::from_static("application/json"));
-        headers.insert("origin", HeaderValue::from_static("https://futures.mexc.com"));
-        headers.insert("referer", HeaderValue::from_static("https://futures.mexc.com/exchange"));
-
+        headers.insert("origin", HeaderValue::from_str(&self.web_url)?);
+        headers.insert("referer", HeaderValue::from_str(&format!("{}/exchange", self.web_url))?);
 
-        let resp: FuturesResponse = self.client.post(url).headers(headers).json(&params).send().await?.json().await?;
 
-        if !resp.success {
-            bail!("mexc futures err resp: {:?}", resp.message);
-        }
-
-        let receipt: OrderReceipt = serde_json::from_value(resp.data.context("Expected data field")?)?;
-
-        Ok(receipt)
+        let resp: Response = self.client.post(url).headers(headers).json(&params).send().await?;
+        decode_futures_response(resp).await
     }
 
-    pub async fn get_open_positions(&self) -> anyhow::Result<Vec<FuturesPosition>> {
-
-        let url = format!("{}/api/v1/private/position/open_positions", FUTURES_API_URL);
-
-        let headers = self.generate_signed_header()?;
-
-        let resp: Response = self.client.get(url).headers(headers).send().await?;
-
-        let json_str: String = resp.text().await?;
-
-        //println!("{json_str}");
-
-        let resp: FuturesResponse = serde_json::from_str(&json_str)?;
-
-        //dbg!(&resp);
-
-        if !resp.success {
-            bail!("mexc futures err resp: {:?}", resp.message);
-        }
-
-        let positions: Vec<FuturesPosition> = serde_json::from_value(resp.data.context("Expected data field")?)?;
-        
-
-        Ok(positions)
+    pub async fn get_open_positions(&self) -> MexcResult<Vec<FuturesPosition>> {
+        self.get_open_positions_as().await
     }
 
-
-    pub async fn get_fair_price(&self, symbol: &str) -> anyhow::Result<f64> {
-        let url = format!("{}/api/v1/contract/index_price/{}", FUTURES_API_URL, symbol);
-        let resp: FuturesResponse = self.client.get(url).send().await?.json().await?;
-
-        if !resp.success {
-            bail!("mexc futures err resp: {:?}", resp.message);
-        }
-        resp.data.context("Expected data field")?.get("indexPrice").context("Expected index price")?.as_f64().context("f64 convert error")
+    /// `get_open_positions` with amounts read into `N`
+    pub async fn get_open_positions_as<N: Numeric>(&self) -> MexcResult<Vec<FuturesPosition<N>>> {
+        self.get_private("/api/v1/private/position/open_positions").await
     }
 
-    pub async fn get_contract_details(&self, symbol: &str) -> anyhow::Result<ContractInfo> {
-
-        let url = format!("{}/api/v1/contract/detail?symbol={}", FUTURES_API_URL,symbol);
-
-        let resp: FuturesResponse = self.client.get(url).send().await?.json().await?;
 
-        if !resp.success {
-            bail!("mexc futures err resp: {:?}", resp.message);
-        }
-        
-        let detail: ContractInfo = serde_json::from_value(resp.data.context("Expected data field")?)?;
+    pub async fn get_fair_price(&self, symbol: &str) -> MexcResult<f64> {
+        let path = format!("/api/v1/contract/index_price/{}", symbol);
+        let index_price: IndexPrice = self.get_public(&path).await?;
+        Ok(index_price.index_price)
+    }
 
-        Ok(detail)
+    pub async fn get_contract_details(&self, symbol: &str) -> MexcResult<ContractInfo> {
+        let path = format!("/api/v1/contract/detail?symbol={}", symbol);
+        self.get_public(&path).await
     }
 
     /* 
     pub async fn get_all_contract_details(&self) -> anyhow::Result<()> {
 
-        let url = format!("{}/api/v1/contract/detail", FUTURES_API_URL);
+        let url = format!("{}/api/v1/contract/detail", self.contract_url);
 
         let resp: FuturesResponse = self.client.get(url).send().await?.json().await?;
 
@@ -313,6 +401,24 @@ impl MexcFutures {
     */
 }
 
+/// Unwraps the `{success, code, data, message}` envelope, mapping unsuccessful replies to `FuturesResponse`
+async fn decode_futures_response<T: DeserializeOwned>(resp: Response) -> MexcResult<T> {
+    let status = resp.status();
+    let body = resp.text().await?;
+
+    let envelope: FuturesResponse = match serde_json::from_str(&body) {
+        Ok(envelope) => envelope,
+        Err(_) if !status.is_success() => return Err(MexcError::Http { status, body }),
+        Err(err) => return Err(MexcError::decode(err, body))
+    };
+
+    if !envelope.success {
+        return Err(MexcError::FuturesResponse { code: envelope.code, message: envelope.message });
+    }
+
+    serde_json::from_value(envelope.data.unwrap_or(Value::Null)).map_err(|err| MexcError::decode(err, body))
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -1,36 +1,39 @@
 use serde::Deserialize;
-use serde_json::Value;
+use serde_json::{json, Value};
+use crate::num::{parse_number, Numeric};
 use crate::utils::parse_string_to_f64;
+use crate::ws::structures::WsKlineInterval;
 
 
-#[derive(Deserialize, Debug)]
-pub struct FuturesBalance {
+#[derive(Deserialize, Debug, Clone)]
+#[serde(bound(deserialize = "N: Numeric"))]
+pub struct FuturesBalance<N = f64> {
     pub currency: String,
 
     #[serde(rename = "positionMargin")]
-    #[serde(deserialize_with = "parse_string_to_f64")]
-    pub position_margin: f64,
+    #[serde(deserialize_with = "parse_number")]
+    pub position_margin: N,
 
     #[serde(rename = "availableBalance")]
-    #[serde(deserialize_with = "parse_string_to_f64")]
-    pub available_balance: f64,
+    #[serde(deserialize_with = "parse_number")]
+    pub available_balance: N,
 
     #[serde(rename = "cashBalance")]
-    #[serde(deserialize_with = "parse_string_to_f64")]
-    pub cash_balance: f64,
+    #[serde(deserialize_with = "parse_number")]
+    pub cash_balance: N,
 
     #[serde(rename = "frozenBalance")]
-    #[serde(deserialize_with = "parse_string_to_f64")]
-    pub frozen_balance: f64,
+    #[serde(deserialize_with = "parse_number")]
+    pub frozen_balance: N,
 
-    #[serde(deserialize_with = "parse_string_to_f64")]
-    pub equity: f64,
+    #[serde(deserialize_with = "parse_number")]
+    pub equity: N,
 
-    #[serde(deserialize_with = "parse_string_to_f64")]
-    pub unrealized: f64,
+    #[serde(deserialize_with = "parse_number")]
+    pub unrealized: N,
 
-    #[serde(deserialize_with = "parse_string_to_f64")]
-    pub bonus: f64,
+    #[serde(deserialize_with = "parse_number")]
+    pub bonus: N,
 }
 
 
@@ -43,66 +46,75 @@ pub struct FuturesResponse {
 }
 
 #[derive(Deserialize, Debug)]
-pub struct FuturesPosition {
+pub struct IndexPrice {
+    pub symbol: String,
+    #[serde(rename = "indexPrice", deserialize_with = "parse_string_to_f64")]
+    pub index_price: f64,
+    pub timestamp: u128
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(bound(deserialize = "N: Numeric"))]
+pub struct FuturesPosition<N = f64> {
     #[serde(rename = "autoAddIm")]
     pub auto_add_im: bool,
 
-    #[serde(rename = "closeAvgPrice", deserialize_with = "parse_string_to_f64")]
-    pub close_avg_price: f64,
+    #[serde(rename = "closeAvgPrice", deserialize_with = "parse_number")]
+    pub close_avg_price: N,
 
-    #[serde(rename = "closeProfitLoss", deserialize_with = "parse_string_to_f64")]
-    pub close_profit_loss: f64,
+    #[serde(rename = "closeProfitLoss", deserialize_with = "parse_number")]
+    pub close_profit_loss: N,
 
-    #[serde(rename = "closeVol", deserialize_with = "parse_string_to_f64")]
-    pub close_vol: f64,
+    #[serde(rename = "closeVol", deserialize_with = "parse_number")]
+    pub close_vol: N,
 
     #[serde(rename = "createTime")]
     pub create_time: u128,
 
-    #[serde(deserialize_with = "parse_string_to_f64")]
-    pub fee: f64,
+    #[serde(deserialize_with = "parse_number")]
+    pub fee: N,
 
-    #[serde(rename = "frozenVol", deserialize_with = "parse_string_to_f64")]
-    pub frozen_vol: f64,
+    #[serde(rename = "frozenVol", deserialize_with = "parse_number")]
+    pub frozen_vol: N,
 
-    #[serde(rename = "holdAvgPrice", deserialize_with = "parse_string_to_f64")]
-    pub hold_avg_price: f64,
+    #[serde(rename = "holdAvgPrice", deserialize_with = "parse_number")]
+    pub hold_avg_price: N,
 
-    #[serde(rename = "holdAvgPriceFullyScale", deserialize_with = "parse_string_to_f64")]
-    pub hold_avg_price_fully_scale: f64,
+    #[serde(rename = "holdAvgPriceFullyScale", deserialize_with = "parse_number")]
+    pub hold_avg_price_fully_scale: N,
 
-    #[serde(rename = "holdFee", deserialize_with = "parse_string_to_f64")]
-    pub hold_fee: f64,
+    #[serde(rename = "holdFee", deserialize_with = "parse_number")]
+    pub hold_fee: N,
 
-    #[serde(rename = "holdVol", deserialize_with = "parse_string_to_f64")]
-    pub hold_vol: f64,
+    #[serde(rename = "holdVol", deserialize_with = "parse_number")]
+    pub hold_vol: N,
 
-    #[serde(deserialize_with = "parse_string_to_f64")]
-    pub im: f64,
+    #[serde(deserialize_with = "parse_number")]
+    pub im: N,
 
-    #[serde(deserialize_with = "parse_string_to_f64")]
-    pub leverage: f64,
+    #[serde(deserialize_with = "parse_number")]
+    pub leverage: N,
 
-    #[serde(rename = "liquidatePrice", deserialize_with = "parse_string_to_f64")]
-    pub liquidate_price: f64,
+    #[serde(rename = "liquidatePrice", deserialize_with = "parse_number")]
+    pub liquidate_price: N,
 
-    #[serde(rename = "marginRatio", deserialize_with = "parse_string_to_f64")]
-    pub margin_ratio: f64,
+    #[serde(rename = "marginRatio", deserialize_with = "parse_number")]
+    pub margin_ratio: N,
 
-    #[serde(rename = "newCloseAvgPrice", deserialize_with = "parse_string_to_f64")]
-    pub new_close_avg_price: f64,
+    #[serde(rename = "newCloseAvgPrice", deserialize_with = "parse_number")]
+    pub new_close_avg_price: N,
 
-    #[serde(rename = "newOpenAvgPrice", deserialize_with = "parse_string_to_f64")]
-    pub new_open_avg_price: f64,
+    #[serde(rename = "newOpenAvgPrice", deserialize_with = "parse_number")]
+    pub new_open_avg_price: N,
 
-    #[serde(deserialize_with = "parse_string_to_f64")]
-    pub oim: f64,
+    #[serde(deserialize_with = "parse_number")]
+    pub oim: N,
 
-    #[serde(rename = "openAvgPrice", deserialize_with = "parse_string_to_f64")]
-    pub open_avg_price: f64,
+    #[serde(rename = "openAvgPrice", deserialize_with = "parse_number")]
+    pub open_avg_price: N,
 
-    #[serde(rename = "openAvgPriceFullyScale", deserialize_with = "parse_string_to_f64")]
-    pub open_avg_price_fully_scale: f64,
+    #[serde(rename = "openAvgPriceFullyScale", deserialize_with = "parse_number")]
+    pub open_avg_price_fully_scale: N,
 
     #[serde(rename = "openType")]
     pub open_type: i32,
@@ -113,11 +125,11 @@ pub struct FuturesPosition {
     #[serde(rename = "positionType")]
     pub position_type: i32,
 
-    #[serde(rename = "profitRatio", deserialize_with = "parse_string_to_f64")]
-    pub profit_ratio: f64,
+    #[serde(rename = "profitRatio", deserialize_with = "parse_number")]
+    pub profit_ratio: N,
 
-    #[serde(deserialize_with = "parse_string_to_f64")]
-    pub realised: f64,
+    #[serde(deserialize_with = "parse_number")]
+    pub realised: N,
 
     pub state: i32,
 
@@ -303,4 +315,334 @@ pub struct OrderReceipt {
     pub order_id: String,
     #[serde(rename = "ts")]
     pub timestamp: u128
-}
\ No newline at end of file
+}
+
+// https://mexcdevelop.github.io/apidocs/contract_v1_en/#websocket-api
+#[derive(Deserialize, Debug, Clone)]
+pub struct FuturesTicker {
+    pub symbol: String,
+    #[serde(rename = "lastPrice", deserialize_with = "parse_string_to_f64")]
+    pub last_price: f64,
+    #[serde(rename = "bid1", deserialize_with = "parse_string_to_f64")]
+    pub bid_price: f64,
+    #[serde(rename = "ask1", deserialize_with = "parse_string_to_f64")]
+    pub ask_price: f64,
+    #[serde(rename = "fairPrice", default, deserialize_with = "parse_string_to_f64")]
+    pub fair_price: f64,
+    #[serde(rename = "indexPrice", default, deserialize_with = "parse_string_to_f64")]
+    pub index_price: f64,
+    #[serde(rename = "fundingRate", default, deserialize_with = "parse_string_to_f64")]
+    pub funding_rate: f64,
+    #[serde(rename = "holdVol", default, deserialize_with = "parse_string_to_f64")]
+    pub hold_vol: f64,
+    #[serde(rename = "volume24", default, deserialize_with = "parse_string_to_f64")]
+    pub volume_24h: f64,
+    #[serde(rename = "amount24", default, deserialize_with = "parse_string_to_f64")]
+    pub amount_24h: f64,
+    #[serde(rename = "riseFallRate", default, deserialize_with = "parse_string_to_f64")]
+    pub rise_fall_rate: f64,
+    #[serde(default)]
+    pub timestamp: u128
+}
+
+/// `[price, contracts, order count]`
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+#[serde(from = "(f64, f64, u32)")]
+pub struct FuturesDepthLevel {
+    pub px: f64,
+    pub vol: f64,
+    pub orders: u32
+}
+
+impl From<(f64, f64, u32)> for FuturesDepthLevel {
+    fn from((px, vol, orders): (f64, f64, u32)) -> Self {
+        Self { px, vol, orders }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct FuturesDepth {
+    #[serde(default)]
+    pub asks: Vec<FuturesDepthLevel>,
+    #[serde(default)]
+    pub bids: Vec<FuturesDepthLevel>,
+    pub version: u64
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct FuturesDeal {
+    #[serde(rename = "p", deserialize_with = "parse_string_to_f64")]
+    pub price: f64,
+    #[serde(rename = "v", deserialize_with = "parse_string_to_f64")]
+    pub vol: f64,
+    /// 1 = buy, 2 = sell
+    #[serde(rename = "T")]
+    pub trade_type: u8,
+    /// 1 = opens a position, 2 = closes one, 3 = neither
+    #[serde(rename = "O", default)]
+    pub open_type: u8,
+    #[serde(rename = "t")]
+    pub time: u128
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct FuturesKline {
+    pub symbol: String,
+    pub interval: String,
+    /// Window start in seconds
+    #[serde(rename = "t")]
+    pub window_start: u64,
+    #[serde(rename = "o", deserialize_with = "parse_string_to_f64")]
+    pub open: f64,
+    #[serde(rename = "c", deserialize_with = "parse_string_to_f64")]
+    pub close: f64,
+    #[serde(rename = "h", deserialize_with = "parse_string_to_f64")]
+    pub high: f64,
+    #[serde(rename = "l", deserialize_with = "parse_string_to_f64")]
+    pub low: f64,
+    /// Volume in contracts
+    #[serde(rename = "q", deserialize_with = "parse_string_to_f64")]
+    pub volume: f64,
+    #[serde(rename = "a", deserialize_with = "parse_string_to_f64")]
+    pub amount: f64
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct FundingRate {
+    pub symbol: String,
+    #[serde(deserialize_with = "parse_string_to_f64")]
+    pub rate: f64,
+    #[serde(rename = "nextSettleTime", default)]
+    pub next_settle_time: u128
+}
+
+/// Payload of the index and fair price pushes
+#[derive(Deserialize, Debug, Clone)]
+pub struct FuturesPrice {
+    pub symbol: String,
+    #[serde(deserialize_with = "parse_string_to_f64")]
+    pub price: f64
+}
+
+/// `push.personal.position` payload, a subset of `FuturesPosition`
+#[derive(Deserialize, Debug, Clone)]
+pub struct FuturesPositionUpdate {
+    #[serde(rename = "positionId")]
+    pub position_id: i64,
+    pub symbol: String,
+    /// 1 = long, 2 = short
+    #[serde(rename = "positionType")]
+    pub position_type: i32,
+    /// 1 = isolated, 2 = cross
+    #[serde(rename = "openType")]
+    pub open_type: i32,
+    /// 1 = holding, 2 = system holding, 3 = closed
+    pub state: i32,
+    #[serde(rename = "holdVol", deserialize_with = "parse_string_to_f64")]
+    pub hold_vol: f64,
+    #[serde(rename = "frozenVol", deserialize_with = "parse_string_to_f64")]
+    pub frozen_vol: f64,
+    #[serde(rename = "closeVol", deserialize_with = "parse_string_to_f64")]
+    pub close_vol: f64,
+    #[serde(rename = "holdAvgPrice", deserialize_with = "parse_string_to_f64")]
+    pub hold_avg_price: f64,
+    #[serde(rename = "openAvgPrice", deserialize_with = "parse_string_to_f64")]
+    pub open_avg_price: f64,
+    #[serde(rename = "closeAvgPrice", deserialize_with = "parse_string_to_f64")]
+    pub close_avg_price: f64,
+    #[serde(rename = "liquidatePrice", deserialize_with = "parse_string_to_f64")]
+    pub liquidate_price: f64,
+    #[serde(deserialize_with = "parse_string_to_f64")]
+    pub oim: f64,
+    #[serde(deserialize_with = "parse_string_to_f64")]
+    pub im: f64,
+    #[serde(rename = "holdFee", deserialize_with = "parse_string_to_f64")]
+    pub hold_fee: f64,
+    #[serde(deserialize_with = "parse_string_to_f64")]
+    pub realised: f64,
+    #[serde(deserialize_with = "parse_string_to_f64")]
+    pub leverage: f64,
+    #[serde(rename = "autoAddIm")]
+    pub auto_add_im: bool,
+    #[serde(rename = "adlLevel", default)]
+    pub adl_level: Option<i32>
+}
+
+/// `push.personal.asset` payload, without the cash balance, equity and unrealized pnl of `FuturesBalance`
+#[derive(Deserialize, Debug, Clone)]
+pub struct FuturesAssetUpdate {
+    pub currency: String,
+    #[serde(rename = "positionMargin", deserialize_with = "parse_string_to_f64")]
+    pub position_margin: f64,
+    #[serde(rename = "availableBalance", deserialize_with = "parse_string_to_f64")]
+    pub available_balance: f64,
+    #[serde(rename = "frozenBalance", deserialize_with = "parse_string_to_f64")]
+    pub frozen_balance: f64,
+    #[serde(deserialize_with = "parse_string_to_f64")]
+    pub bonus: f64
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct FuturesOrderUpdate {
+    #[serde(rename = "orderId")]
+    pub order_id: String,
+    pub symbol: String,
+    #[serde(rename = "positionId", default)]
+    pub position_id: i64,
+    #[serde(deserialize_with = "parse_string_to_f64")]
+    pub price: f64,
+    #[serde(deserialize_with = "parse_string_to_f64")]
+    pub vol: f64,
+    #[serde(rename = "dealVol", default, deserialize_with = "parse_string_to_f64")]
+    pub deal_vol: f64,
+    #[serde(rename = "dealAvgPrice", default, deserialize_with = "parse_string_to_f64")]
+    pub deal_avg_price: f64,
+    #[serde(rename = "remainVol", default, deserialize_with = "parse_string_to_f64")]
+    pub remain_vol: f64,
+    #[serde(default, deserialize_with = "parse_string_to_f64")]
+    pub leverage: f64,
+    /// 1 = open long, 2 = close short, 3 = open short, 4 = close long
+    pub side: i32,
+    #[serde(rename = "orderType")]
+    pub order_type: i32,
+    #[serde(rename = "openType", default)]
+    pub open_type: i32,
+    /// 1 = uninformed, 2 = uncompleted, 3 = completed, 4 = cancelled, 5 = invalid
+    pub state: i32,
+    #[serde(rename = "externalOid", default)]
+    pub external_oid: String,
+    #[serde(rename = "errorCode", default)]
+    pub error_code: i64,
+    #[serde(rename = "takerFee", default, deserialize_with = "parse_string_to_f64")]
+    pub taker_fee: f64,
+    #[serde(rename = "makerFee", default, deserialize_with = "parse_string_to_f64")]
+    pub maker_fee: f64,
+    #[serde(default, deserialize_with = "parse_string_to_f64")]
+    pub profit: f64,
+    #[serde(rename = "createTime", default)]
+    pub create_time: u128,
+    #[serde(rename = "updateTime", default)]
+    pub update_time: u128
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct FuturesStopOrderUpdate {
+    #[serde(rename = "orderId", default)]
+    pub order_id: String,
+    pub symbol: String,
+    #[serde(rename = "positionId", default)]
+    pub position_id: i64,
+    #[serde(rename = "stopLossPrice", default, deserialize_with = "parse_string_to_f64")]
+    pub stop_loss_price: f64,
+    #[serde(rename = "takeProfitPrice", default, deserialize_with = "parse_string_to_f64")]
+    pub take_profit_price: f64,
+    #[serde(rename = "isFinished", default)]
+    pub is_finished: i32
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum FuturesChannel {
+    Ticker { symbol: String },
+    Depth { symbol: String },
+    Deals { symbol: String },
+    Kline { symbol: String, interval: WsKlineInterval },
+    FundingRate { symbol: String },
+    IndexPrice { symbol: String },
+    FairPrice { symbol: String },
+    /// Needs a login, as do the other personal channels
+    Orders,
+    Positions,
+    Assets,
+    StopOrders
+}
+
+impl FuturesChannel {
+
+    pub fn is_private(&self) -> bool {
+        self.filter().is_some()
+    }
+
+    /// Name of the `sub.*` / `unsub.*` method of a public channel
+    pub fn method(&self) -> &'static str {
+        match self {
+            FuturesChannel::Ticker { .. } => "ticker",
+            FuturesChannel::Depth { .. } => "depth",
+            FuturesChannel::Deals { .. } => "deal",
+            FuturesChannel::Kline { .. } => "kline",
+            FuturesChannel::FundingRate { .. } => "funding.rate",
+            FuturesChannel::IndexPrice { .. } => "index.price",
+            FuturesChannel::FairPrice { .. } => "fair.price",
+            FuturesChannel::Orders => "personal.order",
+            FuturesChannel::Positions => "personal.position",
+            FuturesChannel::Assets => "personal.asset",
+            FuturesChannel::StopOrders => "personal.stop.order"
+        }
+    }
+
+    pub fn param(&self) -> Value {
+        match self {
+            FuturesChannel::Kline { symbol, interval } => json!({"symbol": symbol, "interval": interval.to_string()}),
+            FuturesChannel::Ticker { symbol }
+            | FuturesChannel::Depth { symbol }
+            | FuturesChannel::Deals { symbol }
+            | FuturesChannel::FundingRate { symbol }
+            | FuturesChannel::IndexPrice { symbol }
+            | FuturesChannel::FairPrice { symbol } => json!({"symbol": symbol}),
+            _ => json!({})
+        }
+    }
+
+    /// Name used by `personal.filter` for the private channels
+    pub fn filter(&self) -> Option<&'static str> {
+        match self {
+            FuturesChannel::Orders => Some("order"),
+            FuturesChannel::Positions => Some("position"),
+            FuturesChannel::Assets => Some("asset"),
+            FuturesChannel::StopOrders => Some("stop.order"),
+            _ => None
+        }
+    }
+}
+
+/// A push on a subscribed channel
+#[derive(Debug, Clone)]
+pub struct FuturesPush<T> {
+    pub channel: String,
+    pub symbol: String,
+    pub timestamp: u128,
+    pub data: T
+}
+
+#[derive(Debug, Clone)]
+pub enum FuturesWsEvent {
+    Ticker(FuturesPush<FuturesTicker>),
+    Depth(FuturesPush<FuturesDepth>),
+    Deals(FuturesPush<Vec<FuturesDeal>>),
+    Kline(FuturesPush<FuturesKline>),
+    FundingRate(FuturesPush<FundingRate>),
+    IndexPrice(FuturesPush<FuturesPrice>),
+    FairPrice(FuturesPush<FuturesPrice>),
+    Order(FuturesPush<FuturesOrderUpdate>),
+    Position(FuturesPush<FuturesPositionUpdate>),
+    Asset(FuturesPush<FuturesAssetUpdate>),
+    StopOrder(FuturesPush<FuturesStopOrderUpdate>),
+    /// Reply to a request on an `rs.*` channel, `data` is "success" or the rejection reason
+    Reply { channel: String, data: Value },
+    /// The login of the current connection was accepted
+    LoggedIn,
+    /// The connection is (re)established and all subscriptions were sent again
+    Connected,
+    /// The connection dropped, a reconnect is pending
+    Disconnected
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct FuturesFrame {
+    pub channel: String,
+    #[serde(default)]
+    pub data: Value,
+    #[serde(default)]
+    pub symbol: String,
+    #[serde(default)]
+    pub ts: u128
+}
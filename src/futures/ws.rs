@@ -0,0 +1,530 @@
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use std::time::Duration;
+use futures_util::stream::SplitSink;
+use futures_util::{SinkExt, StreamExt};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::Message;
+use crate::{MexcError, MexcResult};
+use crate::ws::{connect, WsStream, DEFAULT_EVENT_BUFFER, DEFAULT_PING_INTERVAL, DEFAULT_RECONNECT_DELAY};
+use super::MexcFutures;
+use super::structures::*;
+
+pub const FUTURES_WS_URL: &str = "wss://contract.mexc.com/edge";
+
+enum Command {
+    Subscribe(Vec<FuturesChannel>),
+    Unsubscribe(Vec<FuturesChannel>),
+    /// Drop the current connection and reconnect
+    Reconnect,
+    Close
+}
+
+enum SessionEnd {
+    Closed,
+    Dropped
+}
+
+// the filter is only accepted once the exchange confirmed the login
+enum Login {
+    Out,
+    Pending,
+    Done
+}
+
+pub struct MexcFuturesWsBuilder {
+    url: String,
+    login: Option<Arc<MexcFutures>>,
+    ping_interval: Duration,
+    reconnect_delay: Duration,
+    event_buffer: usize
+}
+
+impl Default for MexcFuturesWsBuilder {
+    fn default() -> Self {
+        Self {
+            url: FUTURES_WS_URL.to_string(),
+            login: None,
+            ping_interval: DEFAULT_PING_INTERVAL,
+            reconnect_delay: DEFAULT_RECONNECT_DELAY,
+            event_buffer: DEFAULT_EVENT_BUFFER
+        }
+    }
+}
+
+impl MexcFuturesWsBuilder {
+
+    pub fn url(mut self, url: impl Into<String>) -> Self {
+        self.url = url.into();
+        self
+    }
+
+    /// Signs the login with the keys and clock of `client`, needed for the personal channels
+    pub fn login(mut self, client: Arc<MexcFutures>) -> Self {
+        self.login = Some(client);
+        self
+    }
+
+    pub fn ping_interval(mut self, ping_interval: Duration) -> Self {
+        self.ping_interval = ping_interval;
+        self
+    }
+
+    pub fn reconnect_delay(mut self, reconnect_delay: Duration) -> Self {
+        self.reconnect_delay = reconnect_delay;
+        self
+    }
+
+    /// Capacity of the event channel, the connection waits for the consumer when it is full
+    pub fn event_buffer(mut self, event_buffer: usize) -> Self {
+        self.event_buffer = event_buffer;
+        self
+    }
+
+    /// Opens the connection and spawns the task driving it. Events arrive on the returned receiver,
+    /// dropping it shuts the connection down.
+    pub async fn connect(self) -> MexcResult<(MexcFuturesWs, mpsc::Receiver<FuturesWsEvent>)> {
+        if let Some(client) = &self.login {
+            client.api_key.as_ref().ok_or(MexcError::MissingCredentials("api key"))?;
+            client.api_secret.as_ref().ok_or(MexcError::MissingCredentials("secret key"))?;
+        }
+
+        let stream = connect(&self.url).await?;
+
+        let (command_tx, command_rx) = mpsc::unbounded_channel();
+        let (event_tx, event_rx) = mpsc::channel(self.event_buffer.max(1));
+
+        let can_login = self.login.is_some();
+        let worker = Worker {
+            url: self.url,
+            login: self.login,
+            ping_interval: self.ping_interval,
+            reconnect_delay: self.reconnect_delay,
+            channels: BTreeSet::new(),
+            commands: command_rx,
+            events: event_tx
+        };
+        tokio::spawn(worker.run(stream));
+
+        Ok((MexcFuturesWs { commands: command_tx, can_login }, event_rx))
+    }
+}
+
+/// Handle to a futures connection, cheap to clone
+#[derive(Clone)]
+pub struct MexcFuturesWs {
+    commands: mpsc::UnboundedSender<Command>,
+    can_login: bool
+}
+
+impl MexcFuturesWs {
+
+    pub fn builder() -> MexcFuturesWsBuilder {
+        MexcFuturesWsBuilder::default()
+    }
+
+    pub async fn connect() -> MexcResult<(MexcFuturesWs, mpsc::Receiver<FuturesWsEvent>)> {
+        Self::builder().connect().await
+    }
+
+    /// Subscriptions are remembered and sent again after every reconnect, the personal channels
+    /// log in first
+    pub fn subscribe(&self, channels: &[FuturesChannel]) -> MexcResult<()> {
+        if !self.can_login && channels.iter().any(FuturesChannel::is_private) {
+            return Err(MexcError::MissingCredentials("futures websocket login"));
+        }
+        self.send(Command::Subscribe(channels.to_vec()))
+    }
+
+    pub fn unsubscribe(&self, channels: &[FuturesChannel]) -> MexcResult<()> {
+        self.send(Command::Unsubscribe(channels.to_vec()))
+    }
+
+    pub fn reconnect(&self) -> MexcResult<()> {
+        self.send(Command::Reconnect)
+    }
+
+    pub fn close(&self) -> MexcResult<()> {
+        self.send(Command::Close)
+    }
+
+    /// True once the connection task has stopped
+    pub fn is_closed(&self) -> bool {
+        self.commands.is_closed()
+    }
+
+    fn send(&self, command: Command) -> MexcResult<()> {
+        self.commands.send(command).map_err(|_| MexcError::ConnectionClosed)
+    }
+}
+
+type WsSink = SplitSink<WsStream, Message>;
+
+struct Worker {
+    url: String,
+    login: Option<Arc<MexcFutures>>,
+    ping_interval: Duration,
+    reconnect_delay: Duration,
+    channels: BTreeSet<FuturesChannel>,
+    commands: mpsc::UnboundedReceiver<Command>,
+    events: mpsc::Sender<FuturesWsEvent>
+}
+
+impl Worker {
+
+    async fn run(mut self, mut stream: WsStream) {
+        loop {
+            if let SessionEnd::Closed = self.session(stream).await {
+                return;
+            }
+
+            if self.events.send(FuturesWsEvent::Disconnected).await.is_err() {
+                return;
+            }
+
+            stream = match self.reconnect().await {
+                Some(stream) => stream,
+                None => return
+            };
+        }
+    }
+
+    /// Retries the connection until it succeeds, keeps tracking subscription changes meanwhile
+    async fn reconnect(&mut self) -> Option<WsStream> {
+        loop {
+            tokio::select! {
+                command = self.commands.recv() => match command {
+                    None | Some(Command::Close) => return None,
+                    Some(Command::Subscribe(channels)) => self.channels.extend(channels),
+                    Some(Command::Unsubscribe(channels)) => channels.iter().for_each(|channel| { self.channels.remove(channel); }),
+                    Some(Command::Reconnect) => {}
+                },
+                _ = tokio::time::sleep(self.reconnect_delay) => {
+                    if let Ok(stream) = connect(&self.url).await {
+                        return Some(stream);
+                    }
+                }
+            }
+        }
+    }
+
+    fn login_message(&self) -> Option<Message> {
+        let client = self.login.as_ref()?;
+        let api_key = client.api_key.as_ref()?;
+        let timestamp = client.clock.timestamp();
+        let signature = client.sign_v1(timestamp, None).ok()?;
+
+        // subscribe = false turns off the default push of every personal channel, the filter picks them
+        let login = json!({
+            "method": "login",
+            "subscribe": false,
+            "param": {"apiKey": api_key, "reqTime": timestamp.to_string(), "signature": signature}
+        });
+        Some(Message::Text(login.to_string()))
+    }
+
+    fn filter_message(&self) -> Message {
+        let filters: Vec<Value> = self.channels
+            .iter()
+            .filter_map(FuturesChannel::filter)
+            .map(|filter| json!({"filter": filter}))
+            .collect();
+        Message::Text(json!({"method": "personal.filter", "param": {"filters": filters}}).to_string())
+    }
+
+    /// Logs in on first use of a personal channel, the filter follows once `rs.login` succeeded. With no
+    /// personal channel left the filter is not sent, an empty one is not a way to turn pushes off; the
+    /// pushes of dropped channels are discarded instead, see `is_unsubscribed`.
+    async fn send_filter(&self, sink: &mut WsSink, login: &mut Login) -> Result<(), SessionEnd> {
+        match login {
+            Login::Out => {
+                let Some(message) = self.login_message() else {
+                    return Ok(());
+                };
+                *login = Login::Pending;
+                sink.send(message).await.map_err(|_| SessionEnd::Dropped)
+            },
+            Login::Pending => Ok(()),
+            Login::Done if !self.channels.iter().any(FuturesChannel::is_private) => Ok(()),
+            Login::Done => sink.send(self.filter_message()).await.map_err(|_| SessionEnd::Dropped)
+        }
+    }
+
+    /// A personal push of a channel no longer subscribed, the last filter may still let it through
+    fn is_unsubscribed(&self, event: &FuturesWsEvent) -> bool {
+        let channel = match event {
+            FuturesWsEvent::Order(_) => FuturesChannel::Orders,
+            FuturesWsEvent::Position(_) => FuturesChannel::Positions,
+            FuturesWsEvent::Asset(_) => FuturesChannel::Assets,
+            FuturesWsEvent::StopOrder(_) => FuturesChannel::StopOrders,
+            _ => return false
+        };
+        !self.channels.contains(&channel)
+    }
+
+    async fn session(&mut self, stream: WsStream) -> SessionEnd {
+        let (mut sink, mut read) = stream.split();
+        let mut login = Login::Out;
+
+        if self.channels.iter().any(FuturesChannel::is_private) {
+            if let Err(end) = self.send_filter(&mut sink, &mut login).await {
+                return end;
+            }
+        }
+
+        let public: Vec<FuturesChannel> = self.channels.iter().filter(|channel| !channel.is_private()).cloned().collect();
+        for msg in subscription_messages("sub", &public) {
+            if sink.send(msg).await.is_err() {
+                return SessionEnd::Dropped;
+            }
+        }
+
+        if self.events.send(FuturesWsEvent::Connected).await.is_err() {
+            return SessionEnd::Closed;
+        }
+
+        let mut ping = tokio::time::interval_at(tokio::time::Instant::now() + self.ping_interval, self.ping_interval);
+
+        loop {
+            tokio::select! {
+                command = self.commands.recv() => {
+                    let (method, channels) = match command {
+                        None | Some(Command::Close) => {
+                            let _ = sink.send(Message::Close(None)).await;
+                            return SessionEnd::Closed;
+                        },
+                        Some(Command::Reconnect) => {
+                            let _ = sink.send(Message::Close(None)).await;
+                            return SessionEnd::Dropped;
+                        },
+                        Some(Command::Subscribe(channels)) => {
+                            self.channels.extend(channels.iter().cloned());
+                            ("sub", channels)
+                        },
+                        Some(Command::Unsubscribe(channels)) => {
+                            channels.iter().for_each(|channel| { self.channels.remove(channel); });
+                            ("unsub", channels)
+                        }
+                    };
+
+                    if channels.iter().any(FuturesChannel::is_private) {
+                        if let Err(end) = self.send_filter(&mut sink, &mut login).await {
+                            return end;
+                        }
+                    }
+
+                    let public: Vec<FuturesChannel> = channels.into_iter().filter(|channel| !channel.is_private()).collect();
+                    for msg in subscription_messages(method, &public) {
+                        if sink.send(msg).await.is_err() {
+                            return SessionEnd::Dropped;
+                        }
+                    }
+                },
+                _ = ping.tick() => {
+                    if sink.send(Message::Text(json!({"method": "ping"}).to_string())).await.is_err() {
+                        return SessionEnd::Dropped;
+                    }
+                },
+                msg = read.next() => match msg {
+                    Some(Ok(Message::Text(text))) => {
+                        let Some(event) = decode_futures_message(&text) else {
+                            continue;
+                        };
+                        if let FuturesWsEvent::LoggedIn = event {
+                            login = Login::Done;
+                            if let Err(end) = self.send_filter(&mut sink, &mut login).await {
+                                return end;
+                            }
+                        }
+                        if self.is_unsubscribed(&event) {
+                            continue;
+                        }
+                        if self.events.send(event).await.is_err() {
+                            return SessionEnd::Closed;
+                        }
+                    },
+                    // pings are answered by tungstenite on the next read
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return SessionEnd::Dropped,
+                    Some(Ok(_)) => {}
+                }
+            }
+        }
+    }
+}
+
+fn subscription_messages(prefix: &str, channels: &[FuturesChannel]) -> Vec<Message> {
+    channels
+        .iter()
+        .map(|channel| Message::Text(json!({"method": format!("{prefix}.{}", channel.method()), "param": channel.param()}).to_string()))
+        .collect()
+}
+
+/// Decodes a frame, returns `None` for pongs and frames on unknown channels
+pub(crate) fn decode_futures_message(text: &str) -> Option<FuturesWsEvent> {
+    let frame: FuturesFrame = serde_json::from_str(text).ok()?;
+
+    fn push<T: DeserializeOwned>(frame: &FuturesFrame) -> Option<FuturesPush<T>> {
+        Some(FuturesPush {
+            channel: frame.channel.clone(),
+            symbol: frame.symbol.clone(),
+            timestamp: frame.ts,
+            data: serde_json::from_value(frame.data.clone()).ok()?
+        })
+    }
+
+    match frame.channel.as_str() {
+        "pong" => None,
+        "rs.login" if frame.data == "success" => Some(FuturesWsEvent::LoggedIn),
+        "push.ticker" => push(&frame).map(FuturesWsEvent::Ticker),
+        "push.depth" => push(&frame).map(FuturesWsEvent::Depth),
+        "push.deal" => {
+            // a single deal object on older versions of the stream
+            let push = push::<Vec<FuturesDeal>>(&frame).or_else(|| {
+                push::<FuturesDeal>(&frame).map(|push| FuturesPush { channel: push.channel, symbol: push.symbol, timestamp: push.timestamp, data: vec![push.data] })
+            });
+            push.map(FuturesWsEvent::Deals)
+        },
+        "push.kline" => push(&frame).map(FuturesWsEvent::Kline),
+        "push.funding.rate" => push(&frame).map(FuturesWsEvent::FundingRate),
+        "push.index.price" => push(&frame).map(FuturesWsEvent::IndexPrice),
+        "push.fair.price" => push(&frame).map(FuturesWsEvent::FairPrice),
+        "push.personal.order" => push(&frame).map(FuturesWsEvent::Order),
+        "push.personal.position" => push(&frame).map(FuturesWsEvent::Position),
+        "push.personal.asset" => push(&frame).map(FuturesWsEvent::Asset),
+        "push.personal.stop.order" => push(&frame).map(FuturesWsEvent::StopOrder),
+        channel if channel.starts_with("rs.") => Some(FuturesWsEvent::Reply { channel: frame.channel.clone(), data: frame.data.clone() }),
+        _ => None
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    #[test]
+    pub fn test_decode_public_pushes() {
+        let msg = r#"{"channel":"push.ticker","data":{"ask1":6866.5,"bid1":6865,"contractId":1,"fairPrice":6867.4,"fundingRate":0.0008,"high24Price":7223.5,"indexPrice":6861.6,"lastPrice":6865.5,"lower24Price":6756,"maxBidPrice":7073.42,"minAskPrice":6661.37,"riseFallRate":-0.0424,"riseFallValue":-304.5,"symbol":"BTC_USDT","timestamp":1587442022003,"holdVol":2284742,"volume24":164586129},"symbol":"BTC_USDT","ts":1587442022003}"#;
+        let Some(FuturesWsEvent::Ticker(push)) = decode_futures_message(msg) else { panic!("expected ticker") };
+        assert_eq!(push.symbol, "BTC_USDT");
+        assert_eq!(push.data.last_price, 6865.5);
+        assert_eq!(push.data.funding_rate, 0.0008);
+
+        let msg = r#"{"channel":"push.depth","data":{"asks":[[6859.5,3251,1]],"bids":[],"version":96801927},"symbol":"BTC_USDT","ts":1587442022003}"#;
+        let Some(FuturesWsEvent::Depth(push)) = decode_futures_message(msg) else { panic!("expected depth") };
+        assert_eq!(push.data.version, 96801927);
+        assert_eq!(push.data.asks, vec![FuturesDepthLevel { px: 6859.5, vol: 3251.0, orders: 1 }]);
+
+        let msg = r#"{"channel":"push.deal","data":{"M":1,"O":1,"T":1,"p":6866.5,"t":1587442049632,"v":2096},"symbol":"BTC_USDT","ts":1587442022003}"#;
+        let Some(FuturesWsEvent::Deals(push)) = decode_futures_message(msg) else { panic!("expected deals") };
+        assert_eq!(push.data.len(), 1);
+        assert_eq!(push.data[0].vol, 2096.0);
+
+        let msg = r#"{"channel":"push.funding.rate","data":{"rate":0.001,"symbol":"BTC_USDT","nextSettleTime":1587442022003},"symbol":"BTC_USDT","ts":1587442022003}"#;
+        let Some(FuturesWsEvent::FundingRate(push)) = decode_futures_message(msg) else { panic!("expected funding rate") };
+        assert_eq!(push.data.rate, 0.001);
+
+        assert!(decode_futures_message(r#"{"channel":"pong","data":1587453241453}"#).is_none());
+        assert!(matches!(decode_futures_message(r#"{"channel":"rs.login","data":"success","ts":1}"#), Some(FuturesWsEvent::LoggedIn)));
+    }
+
+    #[test]
+    pub fn test_decode_personal_pushes() {
+        let msg = r#"{"channel":"push.personal.position","data":{"autoAddIm":false,"closeAvgPrice":0,"closeVol":0,"frozenVol":0,"holdAvgPrice":6866.5,"holdFee":0,"holdVol":1,"im":0.3433,"leverage":20,"liquidatePrice":0,"oim":0.3433,"openAvgPrice":6866.5,"openType":1,"positionId":1394650,"positionType":1,"realised":-0.0005,"state":1,"symbol":"BTC_USDT"}}"#;
+        let Some(FuturesWsEvent::Position(push)) = decode_futures_message(msg) else { panic!("expected position") };
+        assert_eq!(push.data.position_id, 1394650);
+        assert_eq!(push.data.hold_avg_price, 6866.5);
+        assert_eq!(push.data.adl_level, None);
+        // the REST position needs the fields the push leaves out, e.g. the margin ratio
+        let data: Value = serde_json::from_str::<Value>(msg).unwrap()["data"].clone();
+        assert!(serde_json::from_value::<FuturesPosition>(data).is_err());
+
+        let msg = r#"{"channel":"push.personal.asset","data":{"availableBalance":0.7514236,"bonus":0,"currency":"USDT","frozenBalance":0,"positionMargin":0}}"#;
+        let Some(FuturesWsEvent::Asset(push)) = decode_futures_message(msg) else { panic!("expected asset") };
+        assert_eq!(push.data.currency, "USDT");
+        assert_eq!(push.data.available_balance, 0.7514236);
+
+        let msg = r#"{"channel":"push.personal.order","data":{"category":1,"createTime":1610005069976,"dealAvgPrice":0.731,"dealVol":1,"errorCode":0,"externalOid":"_m_95bc2b72d3784bce8f9efecbdef9fe35","feeCurrency":"USDT","leverage":0,"makerFee":0,"openType":1,"orderId":"102067003631907840","orderMargin":0,"orderType":5,"positionId":1397818,"price":0.707,"profit":-0.0005,"remainVol":0,"side":4,"state":3,"symbol":"CRV_USDT","takerFee":0.00004386,"updateTime":1610005069983,"usedMargin":0,"version":2,"vol":1}}"#;
+        let Some(FuturesWsEvent::Order(push)) = decode_futures_message(msg) else { panic!("expected order") };
+        assert_eq!(push.data.order_id, "102067003631907840");
+        assert_eq!(push.data.state, 3);
+        assert_eq!(push.data.deal_avg_price, 0.731);
+    }
+
+    #[tokio::test]
+    pub async fn test_logs_in_and_resubscribes_after_reconnect() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+
+        let server = tokio::spawn(async move {
+            let mut received = vec![];
+
+            for connection in 0..2 {
+                let (socket, _) = listener.accept().await.unwrap();
+                let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+                let mut methods = vec![];
+                // the login is only confirmed after the next request, a filter sent early would come second
+                let expected = if connection == 0 { 3 } else { 4 };
+                while methods.len() < expected {
+                    let Some(Ok(Message::Text(text))) = ws.next().await else { panic!("expected request") };
+                    let request: Value = serde_json::from_str(&text).unwrap();
+                    methods.push(request["method"].as_str().unwrap().to_string());
+                    if methods.len() == 2 {
+                        ws.send(Message::Text(r#"{"channel":"rs.login","data":"success","ts":1}"#.into())).await.unwrap();
+                    }
+                }
+                if connection == 1 {
+                    // a push of the channel just dropped is not passed on
+                    ws.send(Message::Text(r#"{"channel":"push.personal.order","data":{"category":1,"createTime":1,"dealAvgPrice":0,"dealVol":0,"errorCode":0,"externalOid":"x","feeCurrency":"USDT","leverage":0,"makerFee":0,"openType":1,"orderId":"1","orderMargin":0,"orderType":1,"positionId":0,"price":1,"profit":0,"remainVol":1,"side":1,"state":2,"symbol":"BTC_USDT","takerFee":0,"updateTime":1,"usedMargin":0,"version":1,"vol":1}}"#.into())).await.unwrap();
+                    ws.send(Message::Text(r#"{"channel":"rs.unsub.ticker","data":"success","ts":1}"#.into())).await.unwrap();
+                    while ws.next().await.is_some() {}
+                }
+                received.push(methods);
+            }
+            received
+        });
+
+        let client = MexcFutures::builder().api_key("key").api_secret("secret").build().unwrap();
+        let (ws, mut events) = MexcFuturesWs::builder()
+            .url(url)
+            .login(Arc::new(client))
+            .reconnect_delay(Duration::from_millis(10))
+            .connect().await.unwrap();
+
+        assert!(matches!(events.recv().await, Some(FuturesWsEvent::Connected)));
+        ws.subscribe(&[FuturesChannel::Ticker { symbol: "BTC_USDT".into() }, FuturesChannel::Orders]).unwrap();
+        assert!(matches!(events.recv().await, Some(FuturesWsEvent::LoggedIn)));
+        ws.reconnect().unwrap();
+
+        assert!(matches!(events.recv().await, Some(FuturesWsEvent::Disconnected)));
+        assert!(matches!(events.recv().await, Some(FuturesWsEvent::Connected)));
+        assert!(matches!(events.recv().await, Some(FuturesWsEvent::LoggedIn)));
+
+        // no empty filter goes out when the last personal channel is dropped
+        ws.unsubscribe(&[FuturesChannel::Orders, FuturesChannel::Ticker { symbol: "BTC_USDT".into() }]).unwrap();
+        assert!(matches!(events.recv().await, Some(FuturesWsEvent::Reply { .. })));
+        ws.close().unwrap();
+
+        let received = server.await.unwrap();
+        assert_eq!(received[0], vec!["login", "sub.ticker", "personal.filter"]);
+        assert_eq!(received[1], vec!["login", "sub.ticker", "personal.filter", "unsub.ticker"]);
+    }
+
+    #[tokio::test]
+    pub async fn test_private_channel_needs_login() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+            while ws.next().await.is_some() {}
+        });
+
+        let (ws, _events) = MexcFuturesWs::builder().url(url).connect().await.unwrap();
+        assert!(matches!(ws.subscribe(&[FuturesChannel::Positions]), Err(MexcError::MissingCredentials(_))));
+        assert!(ws.subscribe(&[FuturesChannel::FairPrice { symbol: "BTC_USDT".into() }]).is_ok());
+    }
+}
@@ -4,10 +4,29 @@ pub mod orders;
 pub mod testing;
 pub mod account;
 pub mod futures;
-
+pub mod error;
+pub mod rate_limit;
+pub mod retry;
+pub mod clock;
+pub mod ws;
+pub mod orderbook;
+pub mod symbols;
+pub mod num;
+pub mod wallet;
+pub mod subaccount;
+
+use std::sync::Arc;
 use std::time::{Duration, Instant};
-use reqwest::Client;
+use reqwest::{Client, Method};
 use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use tokio::task::JoinHandle;
+use crate::clock::{ServerClock, CLOCK_SYNC_SAMPLES};
+use crate::error::decode_response;
+use crate::rate_limit::{RateLimits, weights};
+use crate::retry::RetryPolicy;
+
+pub use error::{MexcError, MexcResult};
 
 pub const PROD_API_URL: &str = "https://api.mexc.com";
 
@@ -15,23 +34,92 @@ pub const PROD_API_URL: &str = "https://api.mexc.com";
 pub struct Mexc {
     pub api_key: Option<String>,
     pub api_secret: Option<String>,
+    pub base_url: String,
+    pub rate_limits: RateLimits,
+    pub retry_policy: RetryPolicy,
+    pub clock: ServerClock,
+    /// Order placement and cancels are signed but not sent, see `MexcBuilder::dry_run`
+    pub dry_run: bool,
     pub client: Client
 }
 
-// https://mexcdevelop.github.io/apidocs/spot_v3_en/#header
-
+pub struct MexcBuilder {
+    api_key: Option<String>,
+    api_secret: Option<String>,
+    proxy_url: Option<String>,
+    base_url: String,
+    rate_limits: RateLimits,
+    retry_policy: RetryPolicy,
+    clock: ServerClock,
+    dry_run: bool
+}
 
-#[derive(Deserialize, Debug)]
-pub struct ServerTime {
-    #[serde(rename= "serverTime")]
-    pub timestamp: u128
+impl Default for MexcBuilder {
+    fn default() -> Self {
+        Self {
+            api_key: None,
+            api_secret: None,
+            proxy_url: None,
+            base_url: PROD_API_URL.to_string(),
+            rate_limits: RateLimits::spot(),
+            retry_policy: RetryPolicy::default(),
+            clock: ServerClock::new(),
+            dry_run: false
+        }
+    }
 }
 
-impl Mexc {
+impl MexcBuilder {
 
-    pub fn new(api_key: Option<String>, api_secret: Option<String>, proxy_url: Option<String>) -> anyhow::Result<Self> {
+    pub fn api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    pub fn api_secret(mut self, api_secret: impl Into<String>) -> Self {
+        self.api_secret = Some(api_secret.into());
+        self
+    }
+
+    pub fn proxy_url(mut self, proxy_url: impl Into<String>) -> Self {
+        self.proxy_url = Some(proxy_url.into());
+        self
+    }
+
+    /// Host every spot endpoint is routed through, e.g. a regional mirror or a local test server
+    pub fn base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_string();
+        self
+    }
 
-        let client = match proxy_url {
+    /// Budgets requests are throttled against; pass clones of the same limits to share them between clients
+    pub fn rate_limits(mut self, rate_limits: RateLimits) -> Self {
+        self.rate_limits = rate_limits;
+        self
+    }
+
+    /// Retries are applied to reads; order placement only retries when the policy opts in and a client order id is set
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Clock whose offset is applied to signed timestamps, share it to sync several clients at once
+    pub fn clock(mut self, clock: ServerClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Order placement, batches and cancels are validated and signed but never sent, placements return
+    /// synthetic receipts and cancels the orders as queried from the exchange. Reads still hit the exchange.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    pub fn build(self) -> MexcResult<Mexc> {
+
+        let client = match self.proxy_url {
             Some(url) => {
                 let proxy = reqwest::Proxy::all(url)?;
                 reqwest::Client::builder().proxy(proxy).build()?
@@ -39,28 +127,123 @@ impl Mexc {
             None => reqwest::Client::new()
         };
 
+        Ok(Mexc {
+            api_key: self.api_key,
+            api_secret: self.api_secret,
+            base_url: self.base_url,
+            rate_limits: self.rate_limits,
+            retry_policy: self.retry_policy,
+            clock: self.clock,
+            dry_run: self.dry_run,
+            client
+        })
+    }
+}
 
-        Ok(Self {
+// https://mexcdevelop.github.io/apidocs/spot_v3_en/#header
+
+
+#[derive(Deserialize, Debug)]
+pub struct ServerTime {
+    #[serde(rename= "serverTime")]
+    pub timestamp: u128
+}
+
+impl Mexc {
+
+    pub fn new(api_key: Option<String>, api_secret: Option<String>, proxy_url: Option<String>) -> MexcResult<Self> {
+        MexcBuilder {
             api_key,
             api_secret,
-            client
-        })
+            proxy_url,
+            ..Default::default()
+        }.build()
     }
 
-    pub async fn get_server_time(&self) -> anyhow::Result<u128> {
-        let url = format!("{PROD_API_URL}/api/v3/time");
-        let resp = self.client.get(url).send().await?;
+    pub fn builder() -> MexcBuilder {
+        MexcBuilder::default()
+    }
 
-        let st: ServerTime = resp.json().await?;
+    pub async fn get_server_time(&self) -> MexcResult<u128> {
+        let st: ServerTime = self.public_get("/api/v3/time", None, weights::DEFAULT).await?;
         Ok(st.timestamp)
     }
 
-    pub async fn ping(&self) -> anyhow::Result<Duration> {
-        let url = format!("{PROD_API_URL}/api/v3/ping");
+    /// Measures the offset to the server clock and applies it to all signed requests
+    pub async fn sync_clock(&self) -> MexcResult<i64> {
+        self.clock.sync(CLOCK_SYNC_SAMPLES, || self.get_server_time()).await
+    }
+
+    /// Re-syncs the clock every `interval`; a failed sync keeps the last known offset
+    pub fn spawn_clock_sync(self: Arc<Self>, interval: Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                let _ = self.sync_clock().await;
+            }
+        })
+    }
+
+    /// Round trip of the ping endpoint, an error status or a body that is not json is an error
+    pub async fn ping(&self) -> MexcResult<Duration> {
+        let url = format!("{}/api/v3/ping", self.base_url);
+
+        self.rate_limits.weight.acquire(weights::DEFAULT).await;
 
         let inst = Instant::now();
-        let _ = self.client.get(url).send().await?;
+        let resp = self.client.get(url).send().await?;
+        let _: serde_json::Value = decode_response(resp).await?;
 
         Ok(inst.elapsed())
     }
-}
\ No newline at end of file
+
+    pub(crate) async fn public_get<T: DeserializeOwned>(&self, path: &str, query: Option<&str>, weight: u32) -> MexcResult<T> {
+        let url = match query {
+            Some(query) => format!("{}{path}?{query}", self.base_url),
+            None => format!("{}{path}", self.base_url)
+        };
+
+        self.retry_policy.run(true, || async {
+            self.rate_limits.weight.acquire(weight).await;
+            let resp = self.client.get(&url).send().await?;
+            decode_response(resp).await
+        }).await
+    }
+
+    /// Signed request, retried according to the retry policy when it is a read
+    pub(crate) async fn send_signed<T: DeserializeOwned>(&self, method: Method, path: &str, params: &str, weight: u32) -> MexcResult<T> {
+        let retryable = method == Method::GET;
+        self.send_signed_with_retry(method, path, params, weight, retryable).await
+    }
+
+    pub(crate) async fn send_signed_with_retry<T: DeserializeOwned>(&self, method: Method, path: &str, params: &str, weight: u32, retryable: bool) -> MexcResult<T> {
+        self.retry_policy.run(retryable, || self.send_signed_once(method.clone(), path, params, weight)).await
+    }
+
+    /// Signs `params` like `send_signed` would without sending them, for dry runs
+    pub(crate) fn sign_dry_run(&self, params: &str) -> MexcResult<String> {
+        if self.api_key.is_none() {
+            return Err(MexcError::MissingCredentials("api key"));
+        }
+        self.sign_request(format!("{params}&timestamp={}", self.clock.timestamp()))
+    }
+
+    /// Appends the timestamp to `params`, signs them and sends the request with the api key header
+    async fn send_signed_once<T: DeserializeOwned>(&self, method: Method, path: &str, params: &str, weight: u32) -> MexcResult<T> {
+        // wait before taking the timestamp so throttling never eats into the recv window
+        self.rate_limits.weight.acquire(weight).await;
+        let timestamp = self.clock.timestamp();
+
+        let request = if params.is_empty() {
+            format!("timestamp={timestamp}")
+        } else {
+            format!("{params}&timestamp={timestamp}")
+        };
+        let signed_request = self.sign_request(request)?;
+        let url = format!("{}{path}?{signed_request}", self.base_url);
+
+        let resp = self.send_with_key(method, &url).await?;
+        decode_response(resp).await
+    }
+}
@@ -1,16 +1,23 @@
-use crate::{Mexc, PROD_API_URL, utils::parse_string_to_f64};
+use crate::{Mexc, MexcError, MexcResult, rate_limit::weights, utils::{parse_optional_f64, parse_string_to_f64}};
+use std::fmt::Write;
 use serde::Deserialize;
-use serde::de::{self, Visitor, SeqAccess};
+use serde::de::{self, Visitor, SeqAccess, MapAccess};
 use std::fmt;
+use std::marker::PhantomData;
 use serde::Deserializer;
+use crate::num::{Number, Numeric};
 
 
 
-#[derive(Deserialize, Debug)]
-pub struct Orderbook {
+#[derive(Deserialize, Debug, Clone)]
+#[serde(bound(deserialize = "N: Numeric"))]
+pub struct Orderbook<N = f64> {
+    /// Depth version the snapshot reflects, the websocket diffs continue from here
+    #[serde(rename = "lastUpdateId", default)]
+    pub last_update_id: u64,
     pub timestamp: u128,
-    pub bids: Vec<Level>,
-    pub asks: Vec<Level>
+    pub bids: Vec<Level<N>>,
+    pub asks: Vec<Level<N>>
 }
 
 #[derive(Deserialize, Debug)]
@@ -90,78 +97,455 @@ pub struct SymbolInfo {
     pub taker_commission: f64,
 }
 
-#[derive(Debug)]
-pub struct Level {
-    pub px: f64,
-    pub sz: f64
+#[derive(Debug, Clone, PartialEq)]
+pub struct Level<N = f64> {
+    pub px: N,
+    pub sz: N
 }
 
-impl<'de> Deserialize<'de> for Level {
+impl<'de, N: Numeric> Deserialize<'de> for Level<N> {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
         D: Deserializer<'de>,
     {
-        struct LevelVisitor;
+        struct LevelVisitor<N>(PhantomData<N>);
 
-        impl<'de> Visitor<'de> for LevelVisitor {
-            type Value = Level;
+        impl<'de, N: Numeric> Visitor<'de> for LevelVisitor<N> {
+            type Value = Level<N>;
 
             fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
-                formatter.write_str("a two-element array [px, sz]")
+                formatter.write_str("a two-element array [px, sz] or a {p, v} object")
             }
 
-            fn visit_seq<V>(self, mut seq: V) -> Result<Level, V::Error>
+            fn visit_seq<V>(self, mut seq: V) -> Result<Level<N>, V::Error>
             where
                 V: SeqAccess<'de>,
             {
-                let px: String = seq
+                let Number(px) = seq
                     .next_element()?
                     .ok_or_else(|| de::Error::invalid_length(0, &self))?;
-                let sz: String = seq
+                let Number(sz) = seq
                     .next_element()?
                     .ok_or_else(|| de::Error::invalid_length(1, &self))?;
 
-                let px: f64 = px.parse().map_err(de::Error::custom)?;
-                let sz: f64 = sz.parse().map_err(de::Error::custom)?;
+                Ok(Level { px, sz })
+            }
+
+            // websocket depth pushes use {"p": px, "v": sz}
+            fn visit_map<V>(self, mut map: V) -> Result<Level<N>, V::Error>
+            where
+                V: MapAccess<'de>,
+            {
+                let mut px: Option<Number<N>> = None;
+                let mut sz: Option<Number<N>> = None;
+
+                while let Some(key) = map.next_key::<String>()? {
+                    match key.as_str() {
+                        "p" => px = Some(map.next_value()?),
+                        "v" => sz = Some(map.next_value()?),
+                        _ => { map.next_value::<de::IgnoredAny>()?; }
+                    }
+                }
+
+                let Number(px) = px.ok_or_else(|| de::Error::missing_field("p"))?;
+                let Number(sz) = sz.ok_or_else(|| de::Error::missing_field("v"))?;
 
                 Ok(Level { px, sz })
             }
         }
 
-        deserializer.deserialize_seq(LevelVisitor)
+        deserializer.deserialize_any(LevelVisitor(PhantomData))
     }
 }
 
-impl Mexc {
+/// Upper bound of `limit` on the klines endpoint
+pub const MAX_KLINES_LIMIT: u32 = 1000;
+/// Upper bound of `limit` on the trades endpoints
+pub const MAX_TRADES_LIMIT: u32 = 1000;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Trade {
+    /// Not always disclosed by the exchange
+    pub id: Option<u64>,
+    #[serde(deserialize_with = "parse_string_to_f64")]
+    pub price: f64,
+    #[serde(deserialize_with = "parse_string_to_f64")]
+    pub qty: f64,
+    #[serde(rename = "quoteQty", deserialize_with = "parse_string_to_f64")]
+    pub quote_qty: f64,
+    pub time: u128,
+    #[serde(rename = "isBuyerMaker")]
+    pub is_buyer_maker: bool,
+    #[serde(rename = "isBestMatch")]
+    pub is_best_match: bool
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AggTrade {
+    /// Aggregate trade id, not always disclosed by the exchange
+    #[serde(rename = "a")]
+    pub id: Option<u64>,
+    #[serde(rename = "f")]
+    pub first_trade_id: Option<u64>,
+    #[serde(rename = "l")]
+    pub last_trade_id: Option<u64>,
+    #[serde(rename = "p", deserialize_with = "parse_string_to_f64")]
+    pub price: f64,
+    #[serde(rename = "q", deserialize_with = "parse_string_to_f64")]
+    pub qty: f64,
+    #[serde(rename = "T")]
+    pub time: u128,
+    #[serde(rename = "m")]
+    pub is_buyer_maker: bool,
+    #[serde(rename = "M")]
+    pub is_best_match: bool
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Ticker24hr {
+    pub symbol: String,
+    #[serde(rename = "priceChange", deserialize_with = "parse_string_to_f64")]
+    pub price_change: f64,
+    #[serde(rename = "priceChangePercent", deserialize_with = "parse_string_to_f64")]
+    pub price_change_percent: f64,
+    #[serde(rename = "prevClosePrice", default, deserialize_with = "parse_optional_f64")]
+    pub prev_close_price: Option<f64>,
+    #[serde(rename = "lastPrice", deserialize_with = "parse_string_to_f64")]
+    pub last_price: f64,
+    #[serde(rename = "lastQty", default, deserialize_with = "parse_optional_f64")]
+    pub last_qty: Option<f64>,
+    #[serde(rename = "bidPrice", default, deserialize_with = "parse_optional_f64")]
+    pub bid_price: Option<f64>,
+    #[serde(rename = "bidQty", default, deserialize_with = "parse_optional_f64")]
+    pub bid_qty: Option<f64>,
+    #[serde(rename = "askPrice", default, deserialize_with = "parse_optional_f64")]
+    pub ask_price: Option<f64>,
+    #[serde(rename = "askQty", default, deserialize_with = "parse_optional_f64")]
+    pub ask_qty: Option<f64>,
+    #[serde(rename = "openPrice", deserialize_with = "parse_string_to_f64")]
+    pub open_price: f64,
+    #[serde(rename = "highPrice", deserialize_with = "parse_string_to_f64")]
+    pub high_price: f64,
+    #[serde(rename = "lowPrice", deserialize_with = "parse_string_to_f64")]
+    pub low_price: f64,
+    #[serde(deserialize_with = "parse_string_to_f64")]
+    pub volume: f64,
+    #[serde(rename = "quoteVolume", default, deserialize_with = "parse_optional_f64")]
+    pub quote_volume: Option<f64>,
+    #[serde(rename = "openTime")]
+    pub open_time: u128,
+    #[serde(rename = "closeTime")]
+    pub close_time: u128,
+    pub count: Option<u64>
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct PriceTicker {
+    pub symbol: String,
+    #[serde(deserialize_with = "parse_string_to_f64")]
+    pub price: f64
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct BookTicker {
+    pub symbol: String,
+    #[serde(rename = "bidPrice", deserialize_with = "parse_string_to_f64")]
+    pub bid_price: f64,
+    #[serde(rename = "bidQty", deserialize_with = "parse_string_to_f64")]
+    pub bid_qty: f64,
+    #[serde(rename = "askPrice", deserialize_with = "parse_string_to_f64")]
+    pub ask_price: f64,
+    #[serde(rename = "askQty", deserialize_with = "parse_string_to_f64")]
+    pub ask_qty: f64
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct AvgPrice {
+    /// Averaging window in minutes
+    pub mins: u32,
+    #[serde(deserialize_with = "parse_string_to_f64")]
+    pub price: f64
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KlineInterval {
+    Min1,
+    Min5,
+    Min15,
+    Min30,
+    Min60,
+    Hour4,
+    Day1,
+    Week1,
+    Month1
+}
+
+impl KlineInterval {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KlineInterval::Min1 => "1m",
+            KlineInterval::Min5 => "5m",
+            KlineInterval::Min15 => "15m",
+            KlineInterval::Min30 => "30m",
+            KlineInterval::Min60 => "60m",
+            KlineInterval::Hour4 => "4h",
+            KlineInterval::Day1 => "1d",
+            KlineInterval::Week1 => "1W",
+            KlineInterval::Month1 => "1M"
+        }
+    }
+}
 
-    pub async fn symbol_info(&self, symbol: &str) -> anyhow::Result<ExchangeInfo> {
-        let url = format!("{PROD_API_URL}/api/v3/exchangeInfo?symbol={symbol}");
-        let resp = self.client.get(url).send().await?;
+impl fmt::Display for KlineInterval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Kline {
+    pub open_time: u128,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    pub volume: f64,
+    pub close_time: u128,
+    pub quote_volume: f64
+}
+
+impl<'de> Deserialize<'de> for Kline {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        struct KlineVisitor;
+
+        impl<'de> Visitor<'de> for KlineVisitor {
+            type Value = Kline;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("an array [open time, open, high, low, close, volume, close time, quote volume]")
+            }
+
+            fn visit_seq<V>(self, mut seq: V) -> Result<Kline, V::Error>
+            where
+                V: SeqAccess<'de>,
+            {
+                fn decimal<'de, V: SeqAccess<'de>>(seq: &mut V, index: usize, visitor: &KlineVisitor) -> Result<f64, V::Error> {
+                    let Number(value) = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(index, visitor))?;
+                    Ok(value)
+                }
+
+                let open_time: u128 = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let open = decimal(&mut seq, 1, &self)?;
+                let high = decimal(&mut seq, 2, &self)?;
+                let low = decimal(&mut seq, 3, &self)?;
+                let close = decimal(&mut seq, 4, &self)?;
+                let volume = decimal(&mut seq, 5, &self)?;
+                let close_time: u128 = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(6, &self))?;
+                let quote_volume = decimal(&mut seq, 7, &self)?;
+
+                // ignore any columns added later
+                while seq.next_element::<de::IgnoredAny>()?.is_some() {}
 
-        let exchange_info: ExchangeInfo = resp.json().await?;
-        Ok(exchange_info)
+                Ok(Kline { open_time, open, high, low, close, volume, close_time, quote_volume })
+            }
+        }
+
+        deserializer.deserialize_seq(KlineVisitor)
     }
+}
 
-    pub async fn exchange_info(&self) -> anyhow::Result<ExchangeInfo> {
-        let url = format!("{PROD_API_URL}/api/v3/exchangeInfo");
-        let resp = self.client.get(url).send().await?;
+impl Mexc {
+
+    pub async fn symbol_info(&self, symbol: &str) -> MexcResult<ExchangeInfo> {
+        let query = format!("symbol={symbol}");
+        self.public_get("/api/v3/exchangeInfo", Some(&query), weights::EXCHANGE_INFO).await
+    }
 
-        let exchange_info: ExchangeInfo = resp.json().await?;
-        Ok(exchange_info)
+    pub async fn exchange_info(&self) -> MexcResult<ExchangeInfo> {
+        self.public_get("/api/v3/exchangeInfo", None, weights::EXCHANGE_INFO).await
     }
 
-    pub async fn get_spot_orderbook(&self, symbol: &str, depth: Option<u32>) -> anyhow::Result<Orderbook> {
+    pub async fn get_spot_orderbook(&self, symbol: &str, depth: Option<u32>) -> MexcResult<Orderbook> {
+        self.get_spot_orderbook_as(symbol, depth).await
+    }
+
+    /// `get_spot_orderbook` with prices and sizes read into `N`
+    pub async fn get_spot_orderbook_as<N: Numeric>(&self, symbol: &str, depth: Option<u32>) -> MexcResult<Orderbook<N>> {
 
         // limit: default 100; max 5000
 
-        let url = if let Some(limit) = depth {
-            format!("{PROD_API_URL}/api/v3/depth?symbol={symbol}&limit={limit}")
+        let query = if let Some(limit) = depth {
+            format!("symbol={symbol}&limit={limit}")
         } else {
-            format!("{PROD_API_URL}/api/v3/depth?symbol={symbol}")
+            format!("symbol={symbol}")
         };
-        let resp = self.client.get(url).send().await?;
 
-        let orderbook: Orderbook = resp.json().await?;
-        Ok(orderbook)
+        self.public_get("/api/v3/depth", Some(&query), weights::depth(depth)).await
     }
-}
\ No newline at end of file
+
+    /// Candles of `symbol`, `start` and `end` in milliseconds. limit: default 500; max 1000
+    pub async fn get_klines(&self, symbol: &str, interval: KlineInterval, start: Option<u128>, end: Option<u128>, limit: Option<u32>) -> MexcResult<Vec<Kline>> {
+        let mut query = format!("symbol={symbol}&interval={interval}");
+        if let Some(start) = start {
+            let _ = write!(query, "&startTime={start}");
+        }
+        if let Some(end) = end {
+            let _ = write!(query, "&endTime={end}");
+        }
+        if let Some(limit) = limit {
+            let _ = write!(query, "&limit={limit}");
+        }
+
+        self.public_get("/api/v3/klines", Some(&query), weights::DEFAULT).await
+    }
+
+    /// All candles opening between `start` and `end`, fetched page by page
+    pub async fn get_klines_between(&self, symbol: &str, interval: KlineInterval, start: u128, end: u128) -> MexcResult<Vec<Kline>> {
+        self.get_klines_paged(symbol, interval, start, end, MAX_KLINES_LIMIT).await
+    }
+
+    pub(crate) async fn get_klines_paged(&self, symbol: &str, interval: KlineInterval, start: u128, end: u128, page_size: u32) -> MexcResult<Vec<Kline>> {
+        let mut klines: Vec<Kline> = vec![];
+        let mut cursor = start;
+
+        while cursor <= end {
+            let page = self.get_klines(symbol, interval, Some(cursor), Some(end), Some(page_size)).await?;
+            let full_page = page.len() >= page_size as usize;
+
+            let Some(last) = page.last() else {
+                break;
+            };
+            let next = last.open_time + 1;
+
+            klines.extend(page.into_iter().filter(|kline| kline.open_time >= cursor && kline.open_time <= end));
+
+            if !full_page || next <= cursor {
+                break;
+            }
+            cursor = next;
+        }
+
+        Ok(klines)
+    }
+
+    /// Most recent trades. limit: default 500; max 1000
+    pub async fn get_trades(&self, symbol: &str, limit: Option<u32>) -> MexcResult<Vec<Trade>> {
+        let mut query = format!("symbol={symbol}");
+        if let Some(limit) = limit {
+            let _ = write!(query, "&limit={limit}");
+        }
+
+        self.public_get("/api/v3/trades", Some(&query), weights::TRADES).await
+    }
+
+    /// Older trades. limit: default 500; max 1000
+    pub async fn get_historical_trades(&self, symbol: &str, limit: Option<u32>) -> MexcResult<Vec<Trade>> {
+        let mut query = format!("symbol={symbol}");
+        if let Some(limit) = limit {
+            let _ = write!(query, "&limit={limit}");
+        }
+
+        self.public_get("/api/v3/historicalTrades", Some(&query), weights::DEFAULT).await
+    }
+
+    /// Trades filled at the same price and time by the same taker order, merged.
+    /// `from_id` starts at an aggregate id, `start` and `end` bound the time in milliseconds. limit: default 500; max 1000
+    pub async fn get_agg_trades(&self, symbol: &str, from_id: Option<u64>, start: Option<u128>, end: Option<u128>, limit: Option<u32>) -> MexcResult<Vec<AggTrade>> {
+        let mut query = format!("symbol={symbol}");
+        if let Some(from_id) = from_id {
+            let _ = write!(query, "&fromId={from_id}");
+        }
+        if let Some(start) = start {
+            let _ = write!(query, "&startTime={start}");
+        }
+        if let Some(end) = end {
+            let _ = write!(query, "&endTime={end}");
+        }
+        if let Some(limit) = limit {
+            let _ = write!(query, "&limit={limit}");
+        }
+
+        self.public_get("/api/v3/aggTrades", Some(&query), weights::DEFAULT).await
+    }
+
+    /// All aggregate trades between `start` and `end`, fetched page by page
+    pub async fn get_agg_trades_between(&self, symbol: &str, start: u128, end: u128) -> MexcResult<Vec<AggTrade>> {
+        self.get_agg_trades_paged(symbol, start, end, MAX_TRADES_LIMIT).await
+    }
+
+    /// Pages on `fromId` while the exchange discloses ids, otherwise on time. A time page restarts at
+    /// the millisecond of its predecessor's last trade and skips the trades of that millisecond it already has,
+    /// a millisecond holding a whole page cannot be paged past without ids and is an error.
+    pub(crate) async fn get_agg_trades_paged(&self, symbol: &str, start: u128, end: u128, page_size: u32) -> MexcResult<Vec<AggTrade>> {
+        let mut trades: Vec<AggTrade> = vec![];
+        let mut page = self.get_agg_trades(symbol, None, Some(start), Some(end), Some(page_size)).await?;
+        let mut full_page = page.len() >= page_size as usize;
+
+        loop {
+            let past_end = page.last().is_some_and(|trade| trade.time > end);
+            trades.extend(page.into_iter().filter(|trade| trade.time <= end));
+
+            let Some(last) = trades.last() else {
+                break;
+            };
+            if !full_page || past_end {
+                break;
+            }
+
+            if let Some(id) = last.id {
+                page = self.get_agg_trades(symbol, Some(id + 1), None, None, Some(page_size)).await?;
+                full_page = page.len() >= page_size as usize;
+                continue;
+            }
+
+            let last_time = last.time;
+            let seen = trades.iter().rev().take_while(|trade| trade.time == last_time).count();
+            if seen >= page_size as usize {
+                // moving on would skip the rest of this millisecond
+                return Err(MexcError::InvalidRequest(format!("more than {page_size} trades at {last_time}, cannot page past them")));
+            }
+
+            page = self.get_agg_trades(symbol, None, Some(last_time), Some(end), Some(page_size)).await?;
+            full_page = page.len() >= page_size as usize;
+            page.drain(..seen.min(page.len()));
+        }
+
+        Ok(trades)
+    }
+
+    pub async fn get_ticker_24hr(&self, symbol: &str) -> MexcResult<Ticker24hr> {
+        let query = format!("symbol={symbol}");
+        self.public_get("/api/v3/ticker/24hr", Some(&query), weights::DEFAULT).await
+    }
+
+    pub async fn get_tickers_24hr(&self) -> MexcResult<Vec<Ticker24hr>> {
+        self.public_get("/api/v3/ticker/24hr", None, weights::TICKER_24HR_ALL).await
+    }
+
+    pub async fn get_price_ticker(&self, symbol: &str) -> MexcResult<PriceTicker> {
+        let query = format!("symbol={symbol}");
+        self.public_get("/api/v3/ticker/price", Some(&query), weights::DEFAULT).await
+    }
+
+    pub async fn get_price_tickers(&self) -> MexcResult<Vec<PriceTicker>> {
+        self.public_get("/api/v3/ticker/price", None, weights::TICKER_ALL).await
+    }
+
+    pub async fn get_book_ticker(&self, symbol: &str) -> MexcResult<BookTicker> {
+        let query = format!("symbol={symbol}");
+        self.public_get("/api/v3/ticker/bookTicker", Some(&query), weights::DEFAULT).await
+    }
+
+    pub async fn get_book_tickers(&self) -> MexcResult<Vec<BookTicker>> {
+        self.public_get("/api/v3/ticker/bookTicker", None, weights::TICKER_ALL).await
+    }
+
+    /// Average price over the last minutes, the endpoint has no all-symbols form
+    pub async fn get_avg_price(&self, symbol: &str) -> MexcResult<AvgPrice> {
+        let query = format!("symbol={symbol}");
+        self.public_get("/api/v3/avgPrice", Some(&query), weights::DEFAULT).await
+    }
+}
@@ -0,0 +1,125 @@
+//! Numeric types amounts can be read into. Prices and sizes are `f64` by default; with the `decimal`
+//! feature the `*_as` methods and generic structs also take `Decimal`, which keeps the exchange's
+//! strings exactly.
+
+use std::fmt::{Debug, Display};
+use std::str::FromStr;
+use serde::{Deserialize, Deserializer, Serializer};
+use serde_json::Value;
+
+#[cfg(feature = "decimal")]
+pub use rust_decimal::Decimal;
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// Implemented for `f64` and, with the `decimal` feature, `Decimal`
+pub trait Numeric: sealed::Sealed + Copy + Default + Debug + Display + FromStr + PartialOrd + Send + Sync + 'static {
+    fn from_f64(x: f64) -> Option<Self>;
+    fn to_f64(self) -> f64;
+    /// `self - rhs` without float noise, exact for `Decimal`
+    fn difference(self, rhs: Self) -> Self;
+}
+
+impl sealed::Sealed for f64 {}
+
+impl Numeric for f64 {
+    fn from_f64(x: f64) -> Option<Self> {
+        Some(x)
+    }
+
+    fn to_f64(self) -> f64 {
+        self
+    }
+
+    fn difference(self, rhs: Self) -> Self {
+        // rounded to the decimals the operands were written with, so 100.3 - 40.1 is 60.2
+        let decimals = |x: f64| x.to_string().split_once('.').map_or(0, |(_, fraction)| fraction.len() as i32);
+        let y = 10f64.powi(decimals(self).max(decimals(rhs)));
+        ((self - rhs) * y).round() / y
+    }
+}
+
+#[cfg(feature = "decimal")]
+impl sealed::Sealed for Decimal {}
+
+#[cfg(feature = "decimal")]
+impl Numeric for Decimal {
+    fn from_f64(x: f64) -> Option<Self> {
+        // the shortest representation, what the exchange sent as a json number
+        Decimal::from_str(&x.to_string()).ok()
+    }
+
+    fn to_f64(self) -> f64 {
+        self.to_string().parse().unwrap_or_default()
+    }
+
+    fn difference(self, rhs: Self) -> Self {
+        self - rhs
+    }
+}
+
+pub(crate) fn parse_str<N: Numeric>(s: &str) -> Option<N> {
+    // exponents are rejected by Decimal but may come with json numbers
+    N::from_str(s).ok().or_else(|| s.parse::<f64>().ok().and_then(N::from_f64))
+}
+
+/// Reads a string, number or null (zero) into any `Numeric`
+pub fn parse_number<'de, D, N>(deserializer: D) -> Result<N, D::Error>
+where
+    D: Deserializer<'de>,
+    N: Numeric,
+{
+    let value: Value = Deserialize::deserialize(deserializer)?;
+    match value {
+        Value::String(s) => parse_str(&s).ok_or_else(|| serde::de::Error::custom(format!("Invalid number {s}"))),
+        Value::Number(n) => parse_str(&n.to_string()).ok_or_else(|| serde::de::Error::custom("Invalid number")),
+        Value::Null => Ok(N::default()),
+        _ => Err(serde::de::Error::custom("Invalid type")),
+    }
+}
+
+/// A `Numeric` read with `parse_number`, for hand written deserializers
+pub(crate) struct Number<N>(pub N);
+
+impl<'de, N: Numeric> Deserialize<'de> for Number<N> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        parse_number(deserializer).map(Number)
+    }
+}
+
+/// Writes any `Numeric` as a string, `Decimal`s keep their scale
+pub fn serialize_number<S, N>(x: &N, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+    N: Numeric,
+{
+    serializer.serialize_str(&x.to_string())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::market::Orderbook;
+
+    #[test]
+    pub fn test_parse_numbers() {
+        let book: Orderbook = serde_json::from_str(r#"{"lastUpdateId":1,"timestamp":2,"bids":[["0.3","10"]],"asks":[[0.30000001,1e3]]}"#).unwrap();
+        assert_eq!(book.bids[0].px, 0.3);
+        assert_eq!(book.asks[0].px, 0.30000001);
+        assert_eq!(book.asks[0].sz, 1000.0);
+    }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    pub fn test_parse_decimals() {
+        use super::Decimal;
+
+        let book: Orderbook<Decimal> = serde_json::from_str(r#"{"timestamp":2,"bids":[["0.10","10.000"]],"asks":[[0.2,1e3]]}"#).unwrap();
+        assert_eq!(book.bids[0].px.to_string(), "0.10");
+        assert_eq!(book.bids[0].sz.to_string(), "10.000");
+        assert_eq!((book.bids[0].px + book.asks[0].px).to_string(), "0.30");
+        assert_eq!(book.asks[0].sz, Decimal::from(1000));
+    }
+}
@@ -0,0 +1,312 @@
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, VecDeque};
+use crate::{Mexc, MexcResult};
+use crate::market::{Level, Orderbook};
+use crate::ws::structures::WsDepth;
+
+/// Snapshot depth requested by `sync`, the maximum the depth endpoint serves
+pub const DEFAULT_SNAPSHOT_DEPTH: u32 = 5000;
+/// Diffs buffered while waiting for a snapshot, older ones are dropped beyond this
+pub const MAX_PENDING_UPDATES: usize = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BookSide {
+    Bids,
+    Asks
+}
+
+/// Outcome of feeding a depth diff to the book
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BookUpdate {
+    Applied,
+    /// Already covered by the book
+    Stale,
+    /// No snapshot yet, kept until one is loaded
+    Buffered,
+    /// Versions between `expected` and `received` were missed, the book needs a new snapshot
+    Gap { expected: u64, received: u64 }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Price(f64);
+
+impl PartialEq for Price {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.total_cmp(&other.0) == Ordering::Equal
+    }
+}
+
+impl Eq for Price {}
+
+impl PartialOrd for Price {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Price {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+/// Order book kept in sync from a rest snapshot and the websocket depth diffs.
+///
+/// Feed every `IncreaseDepth` push of the symbol to `apply`. Until a snapshot is loaded, and again
+/// after a gap, diffs are buffered and `is_synced` is false; call `sync` or `load_snapshot` then.
+#[derive(Debug, Clone)]
+pub struct LocalOrderBook {
+    symbol: String,
+    bids: BTreeMap<Price, f64>,
+    asks: BTreeMap<Price, f64>,
+    version: u64,
+    synced: bool,
+    pending: VecDeque<WsDepth>
+}
+
+impl LocalOrderBook {
+
+    pub fn new(symbol: impl Into<String>) -> Self {
+        Self {
+            symbol: symbol.into(),
+            bids: BTreeMap::new(),
+            asks: BTreeMap::new(),
+            version: 0,
+            synced: false,
+            pending: VecDeque::new()
+        }
+    }
+
+    pub fn from_snapshot(symbol: impl Into<String>, snapshot: &Orderbook) -> Self {
+        let mut book = Self::new(symbol);
+        book.load_snapshot(snapshot);
+        book
+    }
+
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    /// Last depth version applied
+    pub fn version(&self) -> u64 {
+        self.version
+    }
+
+    pub fn is_synced(&self) -> bool {
+        self.synced
+    }
+
+    /// Fetches a snapshot and loads it, returns whether the buffered diffs connected to it
+    pub async fn sync(&mut self, client: &Mexc) -> MexcResult<bool> {
+        let snapshot = client.get_spot_orderbook(&self.symbol, Some(DEFAULT_SNAPSHOT_DEPTH)).await?;
+        Ok(self.load_snapshot(&snapshot))
+    }
+
+    /// Replaces the book with `snapshot` and replays the buffered diffs on top.
+    /// Returns false when they left a gap, in which case a newer snapshot is needed.
+    pub fn load_snapshot(&mut self, snapshot: &Orderbook) -> bool {
+        self.bids = snapshot.bids.iter().filter(|l| l.sz > 0.0).map(|l| (Price(l.px), l.sz)).collect();
+        self.asks = snapshot.asks.iter().filter(|l| l.sz > 0.0).map(|l| (Price(l.px), l.sz)).collect();
+        self.version = snapshot.last_update_id;
+        self.synced = true;
+
+        // after a gap the rest is buffered again for the next snapshot
+        for depth in std::mem::take(&mut self.pending) {
+            self.apply(&depth);
+        }
+        self.synced
+    }
+
+    /// Applies a diff when it continues the current version, a zero size removes the level
+    pub fn apply(&mut self, depth: &WsDepth) -> BookUpdate {
+        if !self.synced {
+            if self.pending.len() >= MAX_PENDING_UPDATES {
+                self.pending.pop_front();
+            }
+            self.pending.push_back(depth.clone());
+            return BookUpdate::Buffered;
+        }
+
+        if depth.version <= self.version {
+            return BookUpdate::Stale;
+        }
+
+        let from = depth.from_version.unwrap_or(depth.version);
+        if from > self.version + 1 {
+            let gap = BookUpdate::Gap { expected: self.version + 1, received: from };
+            self.reset();
+            self.pending.push_back(depth.clone());
+            return gap;
+        }
+
+        for level in &depth.bids {
+            update_level(&mut self.bids, level);
+        }
+        for level in &depth.asks {
+            update_level(&mut self.asks, level);
+        }
+        self.version = depth.version;
+        BookUpdate::Applied
+    }
+
+    /// Drops the book, it stays unsynced until the next snapshot
+    pub fn reset(&mut self) {
+        self.bids.clear();
+        self.asks.clear();
+        self.pending.clear();
+        self.version = 0;
+        self.synced = false;
+    }
+
+    pub fn best_bid(&self) -> Option<Level> {
+        self.levels(BookSide::Bids).next()
+    }
+
+    pub fn best_ask(&self) -> Option<Level> {
+        self.levels(BookSide::Asks).next()
+    }
+
+    pub fn mid_price(&self) -> Option<f64> {
+        Some((self.best_bid()?.px + self.best_ask()?.px) / 2.0)
+    }
+
+    pub fn spread(&self) -> Option<f64> {
+        Some(self.best_ask()?.px - self.best_bid()?.px)
+    }
+
+    /// Levels from the best price outwards
+    pub fn levels(&self, side: BookSide) -> Box<dyn Iterator<Item = Level> + '_> {
+        let level = |(px, sz): (&Price, &f64)| Level { px: px.0, sz: *sz };
+        match side {
+            BookSide::Bids => Box::new(self.bids.iter().rev().map(level)),
+            BookSide::Asks => Box::new(self.asks.iter().map(level))
+        }
+    }
+
+    /// Size resting at exactly `px`, zero when there is no level
+    pub fn depth_at_price(&self, side: BookSide, px: f64) -> f64 {
+        let book = match side {
+            BookSide::Bids => &self.bids,
+            BookSide::Asks => &self.asks
+        };
+        book.get(&Price(px)).copied().unwrap_or_default()
+    }
+
+    /// Average price of filling `size` against `side`, `None` when the side holds less than `size`
+    pub fn vwap(&self, side: BookSide, size: f64) -> Option<f64> {
+        if size <= 0.0 {
+            return None;
+        }
+
+        let mut remaining = size;
+        let mut notional = 0.0;
+        for level in self.levels(side) {
+            let fill = remaining.min(level.sz);
+            notional += fill * level.px;
+            remaining -= fill;
+            if remaining <= 0.0 {
+                return Some(notional / size);
+            }
+        }
+        None
+    }
+
+    /// The best `levels` levels with the size summed from the top of the book
+    pub fn cumulative_depth(&self, side: BookSide, levels: usize) -> Vec<Level> {
+        let mut total = 0.0;
+        self.levels(side)
+            .take(levels)
+            .map(|level| {
+                total += level.sz;
+                Level { px: level.px, sz: total }
+            })
+            .collect()
+    }
+
+    /// Total size priced at or better than `px`
+    pub fn depth_within(&self, side: BookSide, px: f64) -> f64 {
+        self.levels(side)
+            .take_while(|level| match side {
+                BookSide::Bids => level.px >= px,
+                BookSide::Asks => level.px <= px
+            })
+            .map(|level| level.sz)
+            .sum()
+    }
+}
+
+fn update_level(book: &mut BTreeMap<Price, f64>, level: &Level) {
+    if level.sz > 0.0 {
+        book.insert(Price(level.px), level.sz);
+    } else {
+        book.remove(&Price(level.px));
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(last_update_id: u64) -> Orderbook {
+        Orderbook {
+            last_update_id,
+            timestamp: 0,
+            bids: vec![Level { px: 99.0, sz: 1.0 }, Level { px: 98.0, sz: 2.0 }, Level { px: 97.0, sz: 3.0 }],
+            asks: vec![Level { px: 101.0, sz: 1.0 }, Level { px: 102.0, sz: 2.0 }, Level { px: 103.0, sz: 3.0 }]
+        }
+    }
+
+    fn diff(from_version: Option<u64>, version: u64, bids: Vec<Level>, asks: Vec<Level>) -> WsDepth {
+        WsDepth { asks, bids, from_version, version }
+    }
+
+    #[test]
+    pub fn test_buffers_until_snapshot() {
+        let mut book = LocalOrderBook::new("BTCUSDT");
+        assert_eq!(book.apply(&diff(Some(8), 9, vec![], vec![Level { px: 101.0, sz: 5.0 }])), BookUpdate::Buffered);
+        assert_eq!(book.apply(&diff(Some(10), 11, vec![Level { px: 99.0, sz: 0.0 }], vec![])), BookUpdate::Buffered);
+
+        // 8..=9 is already in the snapshot, 10..=11 continues it
+        assert!(book.load_snapshot(&snapshot(9)));
+        assert!(book.is_synced());
+        assert_eq!(book.version(), 11);
+        assert_eq!(book.best_bid(), Some(Level { px: 98.0, sz: 2.0 }));
+        assert_eq!(book.best_ask(), Some(Level { px: 101.0, sz: 1.0 }));
+    }
+
+    #[test]
+    pub fn test_detects_gap() {
+        let mut book = LocalOrderBook::from_snapshot("BTCUSDT", &snapshot(100));
+        assert_eq!(book.apply(&diff(None, 100, vec![], vec![])), BookUpdate::Stale);
+        assert_eq!(book.apply(&diff(None, 101, vec![Level { px: 99.5, sz: 1.0 }], vec![])), BookUpdate::Applied);
+        assert_eq!(book.best_bid(), Some(Level { px: 99.5, sz: 1.0 }));
+
+        assert_eq!(book.apply(&diff(Some(105), 107, vec![], vec![])), BookUpdate::Gap { expected: 102, received: 105 });
+        assert!(!book.is_synced());
+        assert_eq!(book.best_bid(), None);
+
+        // a snapshot older than the buffered diff leaves the gap open
+        assert!(!book.load_snapshot(&snapshot(103)));
+        assert!(book.load_snapshot(&snapshot(105)));
+        assert_eq!(book.version(), 107);
+    }
+
+    #[test]
+    pub fn test_queries() {
+        let book = LocalOrderBook::from_snapshot("BTCUSDT", &snapshot(1));
+
+        assert_eq!(book.mid_price(), Some(100.0));
+        assert_eq!(book.spread(), Some(2.0));
+        assert_eq!(book.depth_at_price(BookSide::Bids, 98.0), 2.0);
+        assert_eq!(book.depth_at_price(BookSide::Asks, 98.0), 0.0);
+
+        // 1 @ 101 + 2 @ 102
+        assert_eq!(book.vwap(BookSide::Asks, 3.0), Some(305.0 / 3.0));
+        assert_eq!(book.vwap(BookSide::Bids, 0.5), Some(99.0));
+        assert_eq!(book.vwap(BookSide::Bids, 7.0), None);
+
+        assert_eq!(book.cumulative_depth(BookSide::Bids, 2), vec![Level { px: 99.0, sz: 1.0 }, Level { px: 98.0, sz: 3.0 }]);
+        assert_eq!(book.depth_within(BookSide::Asks, 102.0), 3.0);
+    }
+}
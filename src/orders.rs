@@ -1,22 +1,36 @@
-use crate::{Mexc, PROD_API_URL, utils::{parse_string_to_f64, get_timestamp, serialize_f64_as_string}};
-use anyhow::{anyhow, bail};
-use reqwest::{StatusCode, Response};
+use crate::{Mexc, MexcError, MexcResult, rate_limit::weights, utils::{encode, parse_string_to_f64}};
+use crate::num::{parse_number, parse_str, serialize_number, Numeric};
+use reqwest::{Method, Response};
 use serde::{Deserialize, Serialize};
 use hmac::{Hmac, Mac};
 use sha2::Sha256;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+use std::sync::Arc;
+use std::time::Duration;
+use futures_util::future::join_all;
 
 pub const DEFAULT_RECV_WINDOW: u64 = 5000;
+/// Longest time range a single allOrders request may span
+pub const ALL_ORDERS_MAX_WINDOW: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+/// Upper bound of `limit` on allOrders and myTrades
+pub const MAX_ORDERS_LIMIT: u32 = 1000;
+/// Most orders a single batchOrders request takes, all of one symbol
+pub const MAX_BATCH_ORDERS: usize = 20;
 
 
 
 #[allow(non_camel_case_types)]
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
 pub enum OrderType {
     LIMIT,
     MARKET,
     LIMIT_MAKER,
     IMMEDIATE_OR_CANCEL,
-    FILL_OR_KILL
+    FILL_OR_KILL,
+    /// A type this crate does not know, e.g. stop limit orders; cannot be placed
+    #[serde(other)]
+    UNKNOWN
 }
 impl std::fmt::Display for OrderType {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -26,11 +40,12 @@ impl std::fmt::Display for OrderType {
             OrderType::LIMIT_MAKER => write!(f, "LIMIT_MAKER"),
             OrderType::IMMEDIATE_OR_CANCEL => write!(f, "IMMEDIATE_OR_CANCEL"),
             OrderType::FILL_OR_KILL => write!(f, "FILL_OR_KILL"),
+            OrderType::UNKNOWN => write!(f, "UNKNOWN"),
         }
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Clone, Copy)]
 pub enum OrderSide {
     BUY,
     SELL
@@ -45,27 +60,86 @@ impl std::fmt::Display for OrderSide {
 }
 
 #[allow(non_camel_case_types)]
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, PartialEq, Clone, Copy)]
 pub enum OrderStatus {
     NEW,
     FILLED,
     PARTIALLY_FILLED,
     CANCELED,
-    PARTIALLY_CANCELED
+    PARTIALLY_CANCELED,
+    #[serde(other)]
+    UNKNOWN
+}
+
+impl OrderStatus {
+    /// No further fills can happen
+    pub fn is_final(&self) -> bool {
+        matches!(self, OrderStatus::FILLED | OrderStatus::CANCELED | OrderStatus::PARTIALLY_CANCELED)
+    }
+}
+
+/// Random 32 character id, unique enough to tell apart every order an account places
+pub fn new_client_order_id() -> String {
+    format!("{:032x}", rand::random::<u128>())
+}
+
+/// Identifies an order by the exchange id or by the client order id
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderRef {
+    Id(String),
+    ClientId(String)
+}
+
+impl OrderRef {
+    pub fn id(order_id: impl Into<String>) -> Self {
+        OrderRef::Id(order_id.into())
+    }
+
+    pub fn client_id(client_order_id: impl Into<String>) -> Self {
+        OrderRef::ClientId(client_order_id.into())
+    }
+
+    fn query(&self) -> String {
+        match self {
+            OrderRef::Id(order_id) => format!("orderId={order_id}"),
+            OrderRef::ClientId(client_order_id) => format!("origClientOrderId={}", encode(client_order_id))
+        }
+    }
+}
+
+impl From<&str> for OrderRef {
+    fn from(order_id: &str) -> Self {
+        OrderRef::Id(order_id.to_string())
+    }
+}
+
+impl From<&String> for OrderRef {
+    fn from(order_id: &String) -> Self {
+        OrderRef::Id(order_id.clone())
+    }
+}
+
+impl From<String> for OrderRef {
+    fn from(order_id: String) -> Self {
+        OrderRef::Id(order_id)
+    }
 }
 
 #[allow(dead_code)]
-#[derive(Deserialize, Debug)]
-pub struct OrderReceipt {
+#[derive(Deserialize, Debug, Clone)]
+#[serde(bound(deserialize = "N: Numeric"))]
+pub struct OrderReceipt<N = f64> {
     pub symbol: String,
     #[serde(rename = "orderId")]
     pub order_id: String,
     #[serde(rename = "orderListId")]
     pub order_list_id: i64,
-    #[serde(deserialize_with = "parse_string_to_f64")]
-    pub price: f64,
-    #[serde(rename = "origQty", deserialize_with = "parse_string_to_f64")]
-    pub orig_qty: f64,
+    #[serde(rename = "clientOrderId", default)]
+    pub client_order_id: Option<String>,
+    #[serde(deserialize_with = "parse_number")]
+    pub price: N,
+    #[serde(rename = "origQty", deserialize_with = "parse_number")]
+    pub orig_qty: N,
     #[serde(rename = "type")]
     pub order_type: OrderType,
     pub side: OrderSide,
@@ -75,40 +149,159 @@ pub struct OrderReceipt {
 
 #[allow(dead_code)]
 #[derive(Deserialize, Debug)]
-pub struct CancelledOrder {
+#[serde(bound(deserialize = "N: Numeric"))]
+pub struct CancelledOrder<N = f64> {
     pub symbol: String,
-    //#[serde(rename = "origClientOrderId")]
-    //pub orig_client_order_id: String,
+    #[serde(rename = "origClientOrderId", default)]
+    pub orig_client_order_id: Option<String>,
     #[serde(rename = "orderId")]
     pub order_id: String,
-    //#[serde(rename = "clientOrderId")]
-    //pub client_order_id: String,
-    #[serde(deserialize_with = "parse_string_to_f64")]
-    pub price: f64,
-    #[serde(rename = "origQty", deserialize_with = "parse_string_to_f64")]
-    pub orig_qty: f64,
-    #[serde(rename = "executedQty", deserialize_with = "parse_string_to_f64")]
-    pub exec_qty: f64,
-    #[serde(rename = "cummulativeQuoteQty", deserialize_with = "parse_string_to_f64")]
-    pub cum_quote_qty: f64,
-    //#[serde(rename = "timeInForce")]
-    //pub time_in_force: String,
+    #[serde(rename = "clientOrderId", default)]
+    pub client_order_id: Option<String>,
+    #[serde(deserialize_with = "parse_number")]
+    pub price: N,
+    #[serde(rename = "origQty", deserialize_with = "parse_number")]
+    pub orig_qty: N,
+    #[serde(rename = "executedQty", deserialize_with = "parse_number")]
+    pub exec_qty: N,
+    #[serde(rename = "cummulativeQuoteQty", deserialize_with = "parse_number")]
+    pub cum_quote_qty: N,
+    #[serde(rename = "timeInForce", default)]
+    pub time_in_force: Option<String>,
     #[serde(rename = "type")]
     pub order_type: OrderType,
     pub side: OrderSide,
 }
 
+impl<N: Numeric> OrderReceipt<N> {
+
+    /// Stand-in for a placement skipped by a dry run
+    fn dry_run(order: &OrderRequest, transact_time: u128) -> Self {
+        let amount = |amount: Option<&Amount>| amount.and_then(|amount| parse_str(&amount.text)).unwrap_or_default();
+        let client_order_id = order.client_order_id.clone().unwrap_or_default();
+        Self {
+            symbol: order.symbol.clone(),
+            order_id: format!("dry-run-{client_order_id}"),
+            order_list_id: -1,
+            client_order_id: Some(client_order_id),
+            price: amount(order.price_amount()),
+            orig_qty: amount(order.quantity_amount()),
+            order_type: order.order_type(),
+            side: order.side,
+            transact_time
+        }
+    }
+}
+
+impl<N: Numeric> CancelledOrder<N> {
+
+    /// What cancelling `order` would return, for a cancel skipped by a dry run
+    fn dry_run(order: OrderQuery) -> Self {
+        let amount = |x: f64| N::from_f64(x).unwrap_or_default();
+        Self {
+            symbol: order.symbol,
+            orig_client_order_id: order.client_order_id,
+            order_id: order.order_id,
+            client_order_id: None,
+            price: amount(order.price),
+            orig_qty: amount(order.orig_qty),
+            exec_qty: amount(order.exec_qty),
+            cum_quote_qty: amount(order.cum_quote_qty),
+            time_in_force: order.time_in_force,
+            order_type: order.order_type,
+            side: order.side
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct TestOrderResponse {}
+
+/// An order to move to `price`, see `Mexc::replace_orders`
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderReplace {
+    pub symbol: String,
+    pub order: OrderRef,
+    pub price: f64
+}
+
+impl OrderReplace {
+    pub fn new(symbol: impl Into<String>, order: impl Into<OrderRef>, price: f64) -> Self {
+        Self { symbol: symbol.into(), order: order.into(), price }
+    }
+}
+
+/// What became of the remainder once the old order was cancelled
+#[derive(Debug)]
+pub enum Replacement<N = f64> {
+    Placed(OrderReceipt<N>),
+    /// Nothing was left to place, the old order filled before the cancel
+    Filled,
+    /// The old order is gone but the new one was not placed. Shared by every order of a failed batch.
+    Failed(Arc<MexcError>)
+}
+
+/// Outcome of a cancel-replace: the cancel, the quantity still open at that point and the new order
+#[derive(Debug)]
+pub struct ReplacedOrder<N = f64> {
+    pub cancelled: CancelledOrder<N>,
+    pub remaining_qty: N,
+    pub replacement: Replacement<N>
+}
+
+impl<N: Numeric> ReplacedOrder<N> {
+
+    fn new(cancelled: CancelledOrder<N>) -> Self {
+        // fills are not atomic with the cancel, only what was still open is placed again
+        let remaining_qty = cancelled.orig_qty.difference(cancelled.exec_qty);
+        let remaining_qty = if remaining_qty > N::default() { remaining_qty } else { N::default() };
+        Self { cancelled, remaining_qty, replacement: Replacement::Filled }
+    }
+
+    /// Same side and type as the cancelled order, for the remainder at `price`
+    fn remainder(&self, price: f64) -> MexcResult<Option<OrderRequest>> {
+        if self.remaining_qty <= N::default() {
+            return Ok(None);
+        }
+        let price = N::from_f64(price).ok_or_else(|| MexcError::InvalidRequest(format!("price must be positive, got {price}")))?;
+        let cancelled = &self.cancelled;
+        let order = OrderRequest::from_parts(cancelled.symbol.as_str(), cancelled.side, cancelled.order_type, price, self.remaining_qty)?;
+        Ok(Some(order.with_client_order_id(new_client_order_id())))
+    }
+}
 
 #[derive(Serialize,Deserialize, Debug)]
-pub struct Order {
+#[serde(bound(serialize = "N: Numeric", deserialize = "N: Numeric"))]
+pub struct Order<N = f64> {
     pub symbol: String,
-    #[serde(serialize_with = "serialize_f64_as_string")]
-    pub price: f64,
-    #[serde(serialize_with = "serialize_f64_as_string")]
-    pub quantity: f64,
+    #[serde(serialize_with = "serialize_number", deserialize_with = "parse_number")]
+    pub price: N,
+    #[serde(serialize_with = "serialize_number", deserialize_with = "parse_number")]
+    pub quantity: N,
     pub side: OrderSide,
     #[serde(rename = "type")]
-    pub order_type: OrderType
+    pub order_type: OrderType,
+    /// Generated by `batch_orders` when left empty
+    #[serde(rename = "newClientOrderId", default, skip_serializing_if = "Option::is_none")]
+    pub client_order_id: Option<String>
+}
+
+impl<N: Numeric> Order<N> {
+    pub fn new(symbol: impl Into<String>, side: OrderSide, order_type: OrderType, price: N, quantity: N) -> Self {
+        Self {
+            symbol: symbol.into(),
+            price,
+            quantity,
+            side,
+            order_type,
+            client_order_id: None
+        }
+    }
+
+    pub fn with_client_order_id(mut self, client_order_id: impl Into<String>) -> Self {
+        self.client_order_id = Some(client_order_id.into());
+        self
+    }
 }
 
 #[allow(dead_code)]
@@ -127,6 +320,20 @@ pub struct OrderQuery {
     #[serde(rename = "executedQty", deserialize_with = "parse_string_to_f64")]
     pub exec_qty: f64,
 
+    #[serde(rename = "cummulativeQuoteQty", default, deserialize_with = "parse_string_to_f64")]
+    pub cum_quote_qty: f64,
+
+    pub status: OrderStatus,
+
+    #[serde(rename = "clientOrderId", default)]
+    pub client_order_id: Option<String>,
+
+    #[serde(rename = "timeInForce", default)]
+    pub time_in_force: Option<String>,
+
+    #[serde(rename = "isWorking", default)]
+    pub is_working: bool,
+
     #[serde(rename = "type")]
     pub order_type: OrderType,
     pub side: OrderSide,
@@ -138,11 +345,294 @@ pub struct OrderQuery {
     pub last_update: Option<u128>,
 }
 
+/// One of the account's own fills
+#[derive(Deserialize, Debug, Clone)]
+#[serde(bound(deserialize = "N: Numeric"))]
+pub struct MyTrade<N = f64> {
+    pub symbol: String,
+    pub id: String,
+    #[serde(rename = "orderId")]
+    pub order_id: String,
+    #[serde(rename = "clientOrderId", default)]
+    pub client_order_id: Option<String>,
+    #[serde(deserialize_with = "parse_number")]
+    pub price: N,
+    #[serde(deserialize_with = "parse_number")]
+    pub qty: N,
+    #[serde(rename = "quoteQty", deserialize_with = "parse_number")]
+    pub quote_qty: N,
+    #[serde(deserialize_with = "parse_number")]
+    pub commission: N,
+    #[serde(rename = "commissionAsset")]
+    pub commission_asset: String,
+    pub time: u128,
+    #[serde(rename = "isBuyer")]
+    pub is_buyer: bool,
+    #[serde(rename = "isMaker")]
+    pub is_maker: bool,
+    #[serde(rename = "isSelfTrade", default)]
+    pub is_self_trade: bool
+}
+
+/// An amount as it is sent, `value` is what the checks use
+#[derive(Debug, Clone, PartialEq)]
+struct Amount {
+    value: f64,
+    text: String
+}
+
+impl<N: Numeric> From<N> for Amount {
+    fn from(x: N) -> Self {
+        Self { value: x.to_f64(), text: x.to_string() }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum OrderKind {
+    Limit { price: Amount, quantity: Amount },
+    Market { quantity: Amount },
+    MarketQuote { quote_order_qty: Amount },
+    LimitMaker { price: Amount, quantity: Amount },
+    ImmediateOrCancel { price: Amount, quantity: Amount },
+    FillOrKill { price: Amount, quantity: Amount }
+}
+
+/// A spot order in one of the parameter combinations the exchange accepts, only those parameters
+/// are sent. Amounts must be positive and finite, they are sent as formatted by `N`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderRequest {
+    symbol: String,
+    side: OrderSide,
+    kind: OrderKind,
+    client_order_id: Option<String>
+}
+
+fn check_amount<N: Numeric>(name: &str, value: N) -> MexcResult<Amount> {
+    let amount = Amount::from(value);
+    if amount.value.is_finite() && amount.value > 0.0 {
+        Ok(amount)
+    } else {
+        Err(MexcError::InvalidRequest(format!("{name} must be positive, got {value}")))
+    }
+}
+
+impl OrderRequest {
+
+    fn new(symbol: impl Into<String>, side: OrderSide, kind: OrderKind) -> Self {
+        Self { symbol: symbol.into(), side, kind, client_order_id: None }
+    }
+
+    pub fn limit<N: Numeric>(symbol: impl Into<String>, side: OrderSide, price: N, quantity: N) -> MexcResult<Self> {
+        let kind = OrderKind::Limit { price: check_amount("price", price)?, quantity: check_amount("quantity", quantity)? };
+        Ok(Self::new(symbol, side, kind))
+    }
+
+    /// Market order for `quantity` of the base asset
+    pub fn market<N: Numeric>(symbol: impl Into<String>, side: OrderSide, quantity: N) -> MexcResult<Self> {
+        let kind = OrderKind::Market { quantity: check_amount("quantity", quantity)? };
+        Ok(Self::new(symbol, side, kind))
+    }
+
+    /// Market order spending or receiving `quote_order_qty` of the quote asset
+    pub fn market_quote<N: Numeric>(symbol: impl Into<String>, side: OrderSide, quote_order_qty: N) -> MexcResult<Self> {
+        let kind = OrderKind::MarketQuote { quote_order_qty: check_amount("quoteOrderQty", quote_order_qty)? };
+        Ok(Self::new(symbol, side, kind))
+    }
+
+    /// Post only, rejected when it would take liquidity
+    pub fn limit_maker<N: Numeric>(symbol: impl Into<String>, side: OrderSide, price: N, quantity: N) -> MexcResult<Self> {
+        let kind = OrderKind::LimitMaker { price: check_amount("price", price)?, quantity: check_amount("quantity", quantity)? };
+        Ok(Self::new(symbol, side, kind))
+    }
+
+    pub fn immediate_or_cancel<N: Numeric>(symbol: impl Into<String>, side: OrderSide, price: N, quantity: N) -> MexcResult<Self> {
+        let kind = OrderKind::ImmediateOrCancel { price: check_amount("price", price)?, quantity: check_amount("quantity", quantity)? };
+        Ok(Self::new(symbol, side, kind))
+    }
+
+    pub fn fill_or_kill<N: Numeric>(symbol: impl Into<String>, side: OrderSide, price: N, quantity: N) -> MexcResult<Self> {
+        let kind = OrderKind::FillOrKill { price: check_amount("price", price)?, quantity: check_amount("quantity", quantity)? };
+        Ok(Self::new(symbol, side, kind))
+    }
+
+    /// Builds the request matching `order_type`, a market order uses `quantity` and ignores `price`
+    pub fn from_parts<N: Numeric>(symbol: impl Into<String>, side: OrderSide, order_type: OrderType, price: N, quantity: N) -> MexcResult<Self> {
+        match order_type {
+            OrderType::LIMIT => Self::limit(symbol, side, price, quantity),
+            OrderType::MARKET => Self::market(symbol, side, quantity),
+            OrderType::LIMIT_MAKER => Self::limit_maker(symbol, side, price, quantity),
+            OrderType::IMMEDIATE_OR_CANCEL => Self::immediate_or_cancel(symbol, side, price, quantity),
+            OrderType::FILL_OR_KILL => Self::fill_or_kill(symbol, side, price, quantity),
+            OrderType::UNKNOWN => Err(MexcError::InvalidRequest("unknown order type".into()))
+        }
+    }
+
+    pub fn with_client_order_id(mut self, client_order_id: impl Into<String>) -> Self {
+        self.client_order_id = Some(client_order_id.into());
+        self
+    }
+
+    pub fn symbol(&self) -> &str {
+        &self.symbol
+    }
+
+    pub fn side(&self) -> OrderSide {
+        self.side
+    }
+
+    pub fn client_order_id(&self) -> Option<&str> {
+        self.client_order_id.as_deref()
+    }
+
+    pub fn order_type(&self) -> OrderType {
+        match self.kind {
+            OrderKind::Limit { .. } => OrderType::LIMIT,
+            OrderKind::Market { .. } | OrderKind::MarketQuote { .. } => OrderType::MARKET,
+            OrderKind::LimitMaker { .. } => OrderType::LIMIT_MAKER,
+            OrderKind::ImmediateOrCancel { .. } => OrderType::IMMEDIATE_OR_CANCEL,
+            OrderKind::FillOrKill { .. } => OrderType::FILL_OR_KILL
+        }
+    }
+
+    fn price_amount(&self) -> Option<&Amount> {
+        match &self.kind {
+            OrderKind::Limit { price, .. }
+            | OrderKind::LimitMaker { price, .. }
+            | OrderKind::ImmediateOrCancel { price, .. }
+            | OrderKind::FillOrKill { price, .. } => Some(price),
+            OrderKind::Market { .. } | OrderKind::MarketQuote { .. } => None
+        }
+    }
+
+    fn quantity_amount(&self) -> Option<&Amount> {
+        match &self.kind {
+            OrderKind::Limit { quantity, .. }
+            | OrderKind::Market { quantity }
+            | OrderKind::LimitMaker { quantity, .. }
+            | OrderKind::ImmediateOrCancel { quantity, .. }
+            | OrderKind::FillOrKill { quantity, .. } => Some(quantity),
+            OrderKind::MarketQuote { .. } => None
+        }
+    }
+
+    fn quote_order_qty_amount(&self) -> Option<&Amount> {
+        match &self.kind {
+            OrderKind::MarketQuote { quote_order_qty } => Some(quote_order_qty),
+            _ => None
+        }
+    }
+
+    pub fn price(&self) -> Option<f64> {
+        self.price_amount().map(|price| price.value)
+    }
+
+    /// Base asset quantity, `None` for a market order by quote amount
+    pub fn quantity(&self) -> Option<f64> {
+        self.quantity_amount().map(|quantity| quantity.value)
+    }
+
+    pub fn quote_order_qty(&self) -> Option<f64> {
+        self.quote_order_qty_amount().map(|quote_order_qty| quote_order_qty.value)
+    }
+
+    /// The request with its amounts passed through `price`, `quantity` and `quote_order_qty`, an amount
+    /// they return `None` for keeps its text
+    pub(crate) fn map_amounts<P, Q, A>(&self, price: P, quantity: Q, quote_order_qty: A) -> MexcResult<Self>
+    where
+        P: Fn(f64) -> Option<f64>,
+        Q: Fn(f64) -> Option<f64>,
+        A: Fn(f64) -> Option<f64>
+    {
+        fn map(name: &str, amount: &mut Amount, f: impl Fn(f64) -> Option<f64>) -> MexcResult<()> {
+            if let Some(value) = f(amount.value) {
+                *amount = check_amount(name, value)?;
+            }
+            Ok(())
+        }
+
+        let mut mapped = self.clone();
+        match &mut mapped.kind {
+            OrderKind::Limit { price: p, quantity: q }
+            | OrderKind::LimitMaker { price: p, quantity: q }
+            | OrderKind::ImmediateOrCancel { price: p, quantity: q }
+            | OrderKind::FillOrKill { price: p, quantity: q } => {
+                map("price", p, price)?;
+                map("quantity", q, quantity)?;
+            }
+            OrderKind::Market { quantity: q } => map("quantity", q, quantity)?,
+            OrderKind::MarketQuote { quote_order_qty: a } => map("quoteOrderQty", a, quote_order_qty)?
+        }
+        Ok(mapped)
+    }
+
+    /// Query string of the order parameters, without recvWindow and timestamp
+    pub fn to_query(&self) -> String {
+        let mut query = format!("symbol={}&side={}&type={}", self.symbol, self.side, self.order_type());
+        if let Some(quantity) = self.quantity_amount() {
+            let _ = write!(query, "&quantity={}", quantity.text);
+        }
+        if let Some(quote_order_qty) = self.quote_order_qty_amount() {
+            let _ = write!(query, "&quoteOrderQty={}", quote_order_qty.text);
+        }
+        if let Some(price) = self.price_amount() {
+            let _ = write!(query, "&price={}", price.text);
+        }
+        if let Some(client_order_id) = &self.client_order_id {
+            let _ = write!(query, "&newClientOrderId={}", encode(client_order_id));
+        }
+        query
+    }
+}
+
+#[derive(Serialize)]
+struct BatchOrder<'a> {
+    symbol: &'a str,
+    side: OrderSide,
+    #[serde(rename = "type")]
+    order_type: OrderType,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    quantity: Option<&'a str>,
+    #[serde(rename = "quoteOrderQty", skip_serializing_if = "Option::is_none")]
+    quote_order_qty: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    price: Option<&'a str>,
+    #[serde(rename = "newClientOrderId", skip_serializing_if = "Option::is_none")]
+    client_order_id: Option<&'a str>
+}
+
+impl Serialize for OrderRequest {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        BatchOrder {
+            symbol: &self.symbol,
+            side: self.side,
+            order_type: self.order_type(),
+            quantity: self.quantity_amount().map(|quantity| quantity.text.as_str()),
+            quote_order_qty: self.quote_order_qty_amount().map(|quote_order_qty| quote_order_qty.text.as_str()),
+            price: self.price_amount().map(|price| price.text.as_str()),
+            client_order_id: self.client_order_id.as_deref()
+        }.serialize(serializer)
+    }
+}
+
+/// Checked like `OrderRequest::from_parts`
+impl<N: Numeric> TryFrom<Order<N>> for OrderRequest {
+    type Error = MexcError;
+
+    fn try_from(order: Order<N>) -> MexcResult<Self> {
+        let request = Self::from_parts(order.symbol, order.side, order.order_type, order.price, order.quantity)?;
+        Ok(match order.client_order_id {
+            Some(client_order_id) => request.with_client_order_id(client_order_id),
+            None => request
+        })
+    }
+}
+
 impl Mexc {
 
-    pub fn sign_request(&self, order_details: String) -> anyhow::Result<String> {
-        let secret_key = self.api_secret.as_ref().ok_or_else(|| anyhow!("Missing secret key"))?;
-        let mut signed_key = Hmac::<Sha256>::new_from_slice(secret_key.as_bytes())?;
+    pub fn sign_request(&self, order_details: String) -> MexcResult<String> {
+        let secret_key = self.api_secret.as_ref().ok_or(MexcError::MissingCredentials("secret key"))?;
+        let mut signed_key = Hmac::<Sha256>::new_from_slice(secret_key.as_bytes())
+            .expect("HMAC can take key of any size");
         signed_key.update(order_details.as_bytes());
         let signature = hex::encode(signed_key.finalize().into_bytes());
         let signed_order_details: String = format!("{}&signature={}", order_details, signature);
@@ -158,147 +648,335 @@ impl Mexc {
         Ok(signed_order_details)
     }
     */
-    
-    pub async fn post_signed(&self, url: &str) -> anyhow::Result<Response> {
-        let api_key = self.api_key.as_ref().ok_or_else(|| anyhow!("Missing api key"))?;
+
+    pub(crate) async fn send_with_key(&self, method: Method, url: &str) -> MexcResult<Response> {
+        let api_key = self.api_key.as_ref().ok_or(MexcError::MissingCredentials("api key"))?;
 
         let resp = self.client
-        .post(url)
+        .request(method, url)
         .header("X-MEXC-APIKEY", api_key)
         .send().await?;
         Ok(resp)
     }
+    
+    pub async fn post_signed(&self, url: &str) -> MexcResult<Response> {
+        self.send_with_key(Method::POST, url).await
+    }
 
-    pub async fn put_signed(&self, url: &str) -> anyhow::Result<Response> {
-        let api_key = self.api_key.as_ref().ok_or_else(|| anyhow!("Missing api key"))?;
+    pub async fn put_signed(&self, url: &str) -> MexcResult<Response> {
+        self.send_with_key(Method::PUT, url).await
+    }
 
-        let resp = self.client
-        .put(url)
-        .header("X-MEXC-APIKEY", api_key)
-        .send().await?;
-        Ok(resp)
+    pub async fn delete_signed(&self, url: &str) -> MexcResult<Response> {
+        self.send_with_key(Method::DELETE, url).await
     }
 
-    pub async fn delete_signed(&self, url: &str) -> anyhow::Result<Response> {
-        let api_key = self.api_key.as_ref().ok_or_else(|| anyhow!("Missing api key"))?;
-        
-        let resp = self.client
-        .delete(url)
-        .header("X-MEXC-APIKEY", api_key)
-        .send().await?;
-        Ok(resp)
+    /// Places an order under a generated client order id, echoed in the receipt
+    pub async fn submit_order(&self, symbol: &str, side: OrderSide, order_type: OrderType, price: f64, quantity: f64, recv_window: Option<u64>) -> MexcResult<OrderReceipt> {
+        self.submit_order_with_client_id(symbol, side, order_type, price, quantity, &new_client_order_id(), recv_window).await
+    }
+
+    /// Places an order under `client_order_id`, a market order ignores `price`
+    #[allow(clippy::too_many_arguments)]
+    pub async fn submit_order_with_client_id(&self, symbol: &str, side: OrderSide, order_type: OrderType, price: f64, quantity: f64, client_order_id: &str, recv_window: Option<u64>) -> MexcResult<OrderReceipt> {
+        let order = OrderRequest::from_parts(symbol, side, order_type, price, quantity)?.with_client_order_id(client_order_id);
+        self.place_order(&order, recv_window).await
+    }
+
+    /// Places `order`, under a generated client order id when it has none. Retried when the retry policy
+    /// allows it for orders with a client order id, a placement that went through is then rejected as a duplicate.
+    pub async fn place_order(&self, order: &OrderRequest, recv_window: Option<u64>) -> MexcResult<OrderReceipt> {
+        self.place_order_as(order, recv_window).await
     }
 
-    pub async fn submit_order(&self, symbol: &str, side: OrderSide, order_type: OrderType, price: f64, quantity: f64, recv_window: Option<u64>) -> anyhow::Result<OrderReceipt> {
+    /// `place_order` with the receipt amounts read into `N`
+    pub async fn place_order_as<N: Numeric>(&self, order: &OrderRequest, recv_window: Option<u64>) -> MexcResult<OrderReceipt<N>> {
         let recv_window = recv_window.unwrap_or(DEFAULT_RECV_WINDOW);
-        let timestamp = get_timestamp();
 
-        let order_request = format!("symbol={symbol}&side={side}&type={order_type}&quantity={quantity}&price={price}&recvWindow={recv_window}&timestamp={timestamp}");
-        let signed_order = self.sign_request(order_request)?;
-        let url = format!("{PROD_API_URL}/api/v3/order?{signed_order}");
-        let resp: Response = self.post_signed(&url).await?;
+        let mut order = order.clone();
+        order.client_order_id.get_or_insert_with(new_client_order_id);
+        let order_request = format!("{}&recvWindow={recv_window}", order.to_query());
 
-        if resp.status() == StatusCode::OK {
-            let receipe: OrderReceipt = resp.json().await?;
-            Ok(receipe)
-        } else {
-            let err = resp.text().await?;
-            bail!(err);
+        if self.dry_run {
+            self.sign_dry_run(&order_request)?;
+            return Ok(OrderReceipt::dry_run(&order, self.clock.timestamp()));
         }
+
+        self.rate_limits.orders.acquire(1).await;
+        let retryable = self.retry_policy.retry_with_client_order_id;
+        self.send_signed_with_retry(Method::POST, "/api/v3/order", &order_request, weights::DEFAULT, retryable).await
+    }
+
+    /// Takes `Order`s or `OrderRequest`s, those without a client order id get a generated one. Nothing is
+    /// sent when any `Order` has invalid amounts, or when the batch holds more than `MAX_BATCH_ORDERS`
+    /// orders or more than one symbol.
+    pub async fn batch_orders<O>(&self, orders: Vec<O>, recv_window: Option<u64>) -> MexcResult<Vec<OrderReceipt>>
+    where
+        O: TryInto<OrderRequest>,
+        MexcError: From<O::Error>
+    {
+        self.batch_orders_as(orders, recv_window).await
     }
 
-    pub async fn batch_orders(&self, orders: Vec<Order>, recv_window: Option<u64>) -> anyhow::Result<Vec<OrderReceipt>> {
+    /// `batch_orders` with the receipt amounts read into `N`
+    pub async fn batch_orders_as<N, O>(&self, orders: Vec<O>, recv_window: Option<u64>) -> MexcResult<Vec<OrderReceipt<N>>>
+    where
+        N: Numeric,
+        O: TryInto<OrderRequest>,
+        MexcError: From<O::Error>
+    {
         if orders.is_empty() {
-            bail!("No orders in vector");
+            return Err(MexcError::InvalidRequest("No orders in vector".into()));
+        }
+
+        let orders = orders
+            .into_iter()
+            .map(|order| {
+                let mut order: OrderRequest = order.try_into()?;
+                order.client_order_id.get_or_insert_with(new_client_order_id);
+                Ok(order)
+            })
+            .collect::<MexcResult<Vec<OrderRequest>>>()?;
+
+        if orders.len() > MAX_BATCH_ORDERS {
+            return Err(MexcError::InvalidRequest(format!("{} orders in one batch, at most {MAX_BATCH_ORDERS} allowed", orders.len())));
+        }
+        if orders.iter().any(|order| order.symbol != orders[0].symbol) {
+            return Err(MexcError::InvalidRequest("Batch orders must share one symbol".into()));
         }
 
         let recv_window = recv_window.unwrap_or(DEFAULT_RECV_WINDOW);
-        let timestamp = get_timestamp();
 
-        let json = serde_json::to_string(&orders)?;
+        let json = serde_json::to_string(&orders).map_err(|err| MexcError::InvalidRequest(err.to_string()))?;
 
         let encoded_orders = url::form_urlencoded::Serializer::new(String::new())
         .append_pair("batchOrders", &json)
         .finish();
 
-        let order_request = format!("{encoded_orders}&recvWindow={recv_window}&timestamp={timestamp}");
+        let order_request = format!("{encoded_orders}&recvWindow={recv_window}");
 
-        let signed_order = self.sign_request(order_request)?;
-        let url = format!("{PROD_API_URL}/api/v3/batchOrders?{signed_order}");
+        if self.dry_run {
+            self.sign_dry_run(&order_request)?;
+            let transact_time = self.clock.timestamp();
+            return Ok(orders.iter().map(|order| OrderReceipt::dry_run(order, transact_time)).collect());
+        }
 
-        let resp: Response = self.post_signed(&url).await?;
+        self.rate_limits.orders.acquire(orders.len() as u32).await;
+        let retryable = self.retry_policy.retry_with_client_order_id;
+        self.send_signed_with_retry(Method::POST, "/api/v3/batchOrders", &order_request, weights::DEFAULT, retryable).await
+    }
 
-        if resp.status() == StatusCode::OK {
+    /// Validates `order` against the exchange without placing it
+    pub async fn test_order(&self, order: &OrderRequest, recv_window: Option<u64>) -> MexcResult<()> {
+        let recv_window = recv_window.unwrap_or(DEFAULT_RECV_WINDOW);
 
+        let order_request = format!("{}&recvWindow={recv_window}", order.to_query());
+        let _: TestOrderResponse = self.send_signed(Method::POST, "/api/v3/order/test", &order_request, weights::DEFAULT).await?;
+        Ok(())
+    }
 
-            let txt = resp.text().await?;
-        
-            let res: Result<Vec<OrderReceipt>, _> = serde_json::from_str(&txt);
+    /// A dry run cancels nothing and returns the open orders a cancel would have hit
+    pub async fn cancel_all_orders(&self, symbol: &str, recv_window: Option<u64>) -> MexcResult<Vec<CancelledOrder>> {
+        let recv_window = recv_window.unwrap_or(DEFAULT_RECV_WINDOW);
 
-            match res {
-                Ok(receipts) => Ok(receipts),
-                Err(err) => {
-                    bail!("Err: {err} on {txt}");
-                }
-            }
-        } else {
-            let err = resp.text().await?;
-            bail!(err);
+        let order_request = format!("symbol={symbol}&recvWindow={recv_window}");
+        if self.dry_run {
+            self.sign_dry_run(&order_request)?;
+            let open_orders = self.get_open_orders(symbol, Some(recv_window)).await?;
+            return Ok(open_orders.into_iter().map(CancelledOrder::dry_run).collect());
         }
+        self.send_signed(Method::DELETE, "/api/v3/openOrders", &order_request, weights::DEFAULT).await
     }
 
-    pub async fn cancel_all_orders(&self, symbol: &str, recv_window: Option<u64>) -> anyhow::Result<Vec<CancelledOrder>> {
+    /// Cancels by order id (a plain string) or by client order id. A dry run queries the order instead
+    /// and returns it as it stands, so a dry run `replace_order` places what a live one would.
+    pub async fn cancel_order(&self, symbol: &str, order: impl Into<OrderRef>, recv_window: Option<u64>) -> MexcResult<CancelledOrder> {
+        self.cancel_order_as(symbol, order, recv_window).await
+    }
+
+    /// `cancel_order` with the amounts read into `N`
+    pub async fn cancel_order_as<N: Numeric>(&self, symbol: &str, order: impl Into<OrderRef>, recv_window: Option<u64>) -> MexcResult<CancelledOrder<N>> {
         let recv_window = recv_window.unwrap_or(DEFAULT_RECV_WINDOW);
-        let timestamp = get_timestamp();
 
-        let order_request = format!("symbol={symbol}&recvWindow={recv_window}&timestamp={timestamp}");
-        let signed_order = self.sign_request(order_request)?;
-        let url = format!("{PROD_API_URL}/api/v3/openOrders?{signed_order}");
-        let resp: Response = self.delete_signed(&url).await?;
+        let order = order.into();
+        let order_request = format!("symbol={symbol}&{}&recvWindow={recv_window}", order.query());
+        if self.dry_run {
+            self.sign_dry_run(&order_request)?;
+            let open_order = self.get_order(symbol, order, Some(recv_window)).await?;
+            if open_order.status.is_final() {
+                return Err(MexcError::InvalidRequest(format!("order {} is already {:?}", open_order.order_id, open_order.status)));
+            }
+            return Ok(CancelledOrder::dry_run(open_order));
+        }
+        self.send_signed(Method::DELETE, "/api/v3/order", &order_request, weights::DEFAULT).await
+    }
+
+    /// Cancels `order` and places what was still open of it at `price`, same side and type. The two
+    /// steps are not atomic: an error means nothing was cancelled, once the cancel went through a failed
+    /// placement is reported in the result.
+    pub async fn replace_order(&self, symbol: &str, order: impl Into<OrderRef>, price: f64, recv_window: Option<u64>) -> MexcResult<ReplacedOrder> {
+        self.replace_order_as(symbol, order, price, recv_window).await
+    }
+
+    /// `replace_order` with the amounts read into `N`, for `Decimal` the remaining quantity is exact
+    pub async fn replace_order_as<N: Numeric>(&self, symbol: &str, order: impl Into<OrderRef>, price: f64, recv_window: Option<u64>) -> MexcResult<ReplacedOrder<N>> {
+        let cancelled = self.cancel_order_as(symbol, order, recv_window).await?;
+        let mut replaced = ReplacedOrder::new(cancelled);
+
+        replaced.replacement = match replaced.remainder(price) {
+            Ok(None) => Replacement::Filled,
+            Ok(Some(order)) => match self.place_order_as(&order, recv_window).await {
+                Ok(receipt) => Replacement::Placed(receipt),
+                Err(err) => Replacement::Failed(Arc::new(err))
+            },
+            Err(err) => Replacement::Failed(Arc::new(err))
+        };
+        Ok(replaced)
+    }
+
+    /// `replace_order` for many orders, of any symbols. The cancels run concurrently, the remainders are
+    /// then placed through batchOrders per symbol. Results are in the order of `replaces`.
+    pub async fn replace_orders(&self, replaces: Vec<OrderReplace>, recv_window: Option<u64>) -> Vec<MexcResult<ReplacedOrder>> {
+        self.replace_orders_as(replaces, recv_window).await
+    }
+
+    /// `replace_orders` with the amounts read into `N`
+    pub async fn replace_orders_as<N: Numeric>(&self, replaces: Vec<OrderReplace>, recv_window: Option<u64>) -> Vec<MexcResult<ReplacedOrder<N>>> {
+        let cancels = replaces.iter().map(|replace| self.cancel_order_as(&replace.symbol, replace.order.clone(), recv_window));
+        let mut results: Vec<MexcResult<ReplacedOrder<N>>> = join_all(cancels).await
+            .into_iter()
+            .map(|cancelled| cancelled.map(ReplacedOrder::new))
+            .collect();
+
+        // remainders by symbol, with the index of the result they belong to
+        let mut by_symbol: BTreeMap<&str, Vec<(usize, OrderRequest)>> = BTreeMap::new();
+        for (index, (result, replace)) in results.iter_mut().zip(&replaces).enumerate() {
+            let Ok(replaced) = result else { continue };
+            match replaced.remainder(replace.price) {
+                Ok(Some(order)) => by_symbol.entry(replace.symbol.as_str()).or_default().push((index, order)),
+                Ok(None) => {}
+                Err(err) => replaced.replacement = Replacement::Failed(Arc::new(err))
+            }
+        }
 
-        if resp.status() == StatusCode::OK {
-            let cancelled_orders: Vec<CancelledOrder> = resp.json().await?;
-            Ok(cancelled_orders)
-        } else {
-            let err = resp.text().await?;
-            bail!(err);
+        let batches: Vec<_> = by_symbol.into_values()
+            .flat_map(|orders| orders.chunks(MAX_BATCH_ORDERS).map(<[_]>::to_vec).collect::<Vec<_>>())
+            .collect();
+        let placements = batches.iter().map(|batch| {
+            let orders = batch.iter().map(|(_, order)| order.clone()).collect();
+            self.batch_orders_as(orders, recv_window)
+        });
+
+        for (batch, placed) in batches.iter().zip(join_all(placements).await) {
+            let outcome = placed.map_err(Arc::new);
+            for (index, order) in batch {
+                let Ok(replaced) = &mut results[*index] else { continue };
+                replaced.replacement = match &outcome {
+                    Ok(receipts) => receipts.iter()
+                        .find(|receipt| receipt.client_order_id.as_deref() == order.client_order_id())
+                        .map(|receipt| Replacement::Placed(receipt.clone()))
+                        .unwrap_or_else(|| Replacement::Failed(Arc::new(MexcError::InvalidRequest(format!("no receipt for {}", order.client_order_id().unwrap_or_default()))))),
+                    Err(err) => Replacement::Failed(err.clone())
+                };
+            }
         }
+        results
     }
 
-    pub async fn cancel_order(&self, symbol: &str, order_id: &str,recv_window: Option<u64>) -> anyhow::Result<CancelledOrder> {
+    pub async fn get_open_orders(&self, symbol: &str,recv_window: Option<u64>) -> MexcResult<Vec<OrderQuery>> {
+
         let recv_window = recv_window.unwrap_or(DEFAULT_RECV_WINDOW);
-        let timestamp = get_timestamp();
 
-        let order_request = format!("symbol={symbol}&orderId={order_id}&recvWindow={recv_window}&timestamp={timestamp}");
-        let signed_order = self.sign_request(order_request)?;
-        let url = format!("{PROD_API_URL}/api/v3/order?{signed_order}");
-        let resp: Response = self.delete_signed(&url).await?;
+        let order_request = format!("symbol={symbol}&recvWindow={recv_window}");
+        self.send_signed(Method::GET, "/api/v3/openOrders", &order_request, weights::OPEN_ORDERS).await
+    }
+
+    pub async fn get_order(&self, symbol: &str, order: impl Into<OrderRef>, recv_window: Option<u64>) -> MexcResult<OrderQuery> {
+        let recv_window = recv_window.unwrap_or(DEFAULT_RECV_WINDOW);
 
-        if resp.status() == StatusCode::OK {
-            let cancelled_order: CancelledOrder = resp.json().await?;
-            Ok(cancelled_order)
-        } else {
-            let err = resp.text().await?;
-            bail!(err);
+        let order_request = format!("symbol={symbol}&{}&recvWindow={recv_window}", order.into().query());
+        self.send_signed(Method::GET, "/api/v3/order", &order_request, weights::QUERY_ORDER).await
+    }
+
+    /// Orders of `symbol` created between `start` and `end`, at most 7 days apart. Without a range
+    /// the last 24 hours are returned. limit: default 500; max 1000
+    pub async fn get_all_orders(&self, symbol: &str, start: Option<u128>, end: Option<u128>, limit: Option<u32>, recv_window: Option<u64>) -> MexcResult<Vec<OrderQuery>> {
+        let recv_window = recv_window.unwrap_or(DEFAULT_RECV_WINDOW);
+
+        let mut order_request = format!("symbol={symbol}");
+        if let Some(start) = start {
+            let _ = write!(order_request, "&startTime={start}");
         }
+        if let Some(end) = end {
+            let _ = write!(order_request, "&endTime={end}");
+        }
+        if let Some(limit) = limit {
+            let _ = write!(order_request, "&limit={limit}");
+        }
+        let _ = write!(order_request, "&recvWindow={recv_window}");
+
+        self.send_signed(Method::GET, "/api/v3/allOrders", &order_request, weights::ALL_ORDERS).await
     }
 
-    pub async fn get_open_orders(&self, symbol: &str,recv_window: Option<u64>) -> anyhow::Result<Vec<OrderQuery>> {
+    /// All orders created between `start` and `end`, split into 7 day windows and paged within each.
+    /// Fails when more orders than a page holds share one millisecond, rather than leaving some out.
+    pub async fn get_all_orders_between(&self, symbol: &str, start: u128, end: u128) -> MexcResult<Vec<OrderQuery>> {
+        self.get_all_orders_paged(symbol, start, end, MAX_ORDERS_LIMIT).await
+    }
 
-        let recv_window = recv_window.unwrap_or(DEFAULT_RECV_WINDOW);
-        let timestamp = get_timestamp();
+    pub(crate) async fn get_all_orders_paged(&self, symbol: &str, start: u128, end: u128, page_size: u32) -> MexcResult<Vec<OrderQuery>> {
+        let max_window = ALL_ORDERS_MAX_WINDOW.as_millis() - 1;
+        let mut orders: Vec<OrderQuery> = vec![];
+        let mut seen: HashSet<String> = HashSet::new();
+        let mut cursor = start;
+
+        while cursor <= end {
+            let window_end = end.min(cursor + max_window);
+            let page = self.get_all_orders(symbol, Some(cursor), Some(window_end), Some(page_size), None).await?;
+            let full_page = page.len() >= page_size as usize;
+            let last_time = page.iter().map(|order| order.created_time).max();
+
+            orders.extend(page.into_iter().filter(|order| seen.insert(order.order_id.clone())));
+
+            // a full page continues from its newest order, inclusive since several may share the millisecond
+            cursor = match last_time {
+                Some(last_time) if full_page && last_time > cursor => last_time,
+                Some(_) if full_page => {
+                    // moving on would skip the rest of this millisecond
+                    return Err(MexcError::InvalidRequest(format!("more than {page_size} orders created at {cursor}, cannot page past them")));
+                }
+                _ => window_end + 1
+            };
+        }
+
+        orders.sort_by_key(|order| order.created_time);
+        Ok(orders)
+    }
+
+    /// Fills of `symbol`, of a single order when `order_id` is given. limit: default 500; max 1000
+    pub async fn get_my_trades(&self, symbol: &str, order_id: Option<&str>, start: Option<u128>, end: Option<u128>, limit: Option<u32>, recv_window: Option<u64>) -> MexcResult<Vec<MyTrade>> {
+        self.get_my_trades_as(symbol, order_id, start, end, limit, recv_window).await
+    }
 
-        let order_request = format!("symbol={symbol}&recvWindow={recv_window}&timestamp={timestamp}");
-        let signed_order = self.sign_request(order_request)?;
-        let url = format!("{PROD_API_URL}/api/v3/openOrders?{signed_order}");
-        let resp: Response = self.get_signed(&url).await?;
+    /// `get_my_trades` with prices, quantities and commissions read into `N`
+    pub async fn get_my_trades_as<N: Numeric>(&self, symbol: &str, order_id: Option<&str>, start: Option<u128>, end: Option<u128>, limit: Option<u32>, recv_window: Option<u64>) -> MexcResult<Vec<MyTrade<N>>> {
+        let recv_window = recv_window.unwrap_or(DEFAULT_RECV_WINDOW);
 
-        if resp.status() == StatusCode::OK {
-            let orders: Vec<OrderQuery> = resp.json().await?;
-            Ok(orders)
-        } else {
-            let err = resp.text().await?;
-            bail!(err);
+        let mut order_request = format!("symbol={symbol}");
+        if let Some(order_id) = order_id {
+            let _ = write!(order_request, "&orderId={order_id}");
+        }
+        if let Some(start) = start {
+            let _ = write!(order_request, "&startTime={start}");
+        }
+        if let Some(end) = end {
+            let _ = write!(order_request, "&endTime={end}");
         }
+        if let Some(limit) = limit {
+            let _ = write!(order_request, "&limit={limit}");
+        }
+        let _ = write!(order_request, "&recvWindow={recv_window}");
+
+        self.send_signed(Method::GET, "/api/v3/myTrades", &order_request, weights::MY_TRADES).await
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,188 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+// https://mexcdevelop.github.io/apidocs/spot_v3_en/#limits
+pub const SPOT_WEIGHT_PER_WINDOW: u32 = 500;
+pub const SPOT_ORDERS_PER_WINDOW: u32 = 50;
+pub const SPOT_WINDOW: Duration = Duration::from_secs(10);
+
+// https://mexcdevelop.github.io/apidocs/contract_v1_en/#access-restriction
+pub const FUTURES_REQUESTS_PER_WINDOW: u32 = 20;
+pub const FUTURES_WINDOW: Duration = Duration::from_secs(2);
+
+/// Endpoint weights as listed in the spot api documentation
+pub mod weights {
+    pub const DEFAULT: u32 = 1;
+    pub const EXCHANGE_INFO: u32 = 10;
+    pub const ACCOUNT: u32 = 10;
+    pub const OPEN_ORDERS: u32 = 3;
+    pub const QUERY_ORDER: u32 = 2;
+    pub const ALL_ORDERS: u32 = 10;
+    pub const MY_TRADES: u32 = 10;
+    pub const TRADES: u32 = 5;
+    pub const TICKER_24HR_ALL: u32 = 40;
+    pub const TICKER_ALL: u32 = 2;
+    pub const CAPITAL_CONFIG: u32 = 10;
+    pub const DEPOSIT_ADDRESS: u32 = 10;
+    pub const TRADE_FEE: u32 = 20;
+    pub const DUST_CONVERT: u32 = 10;
+
+    /// Depth weight grows with the requested number of levels
+    pub fn depth(limit: Option<u32>) -> u32 {
+        match limit.unwrap_or(100) {
+            0..=100 => 1,
+            101..=500 => 5,
+            501..=1000 => 10,
+            _ => 50
+        }
+    }
+}
+
+struct Bucket {
+    capacity: f64,
+    tokens: f64,
+    refill_per_sec: f64,
+    last_refill: Instant
+}
+
+impl Bucket {
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.last_refill = now;
+    }
+}
+
+/// Token bucket refilling `capacity` tokens evenly over `window`. Clones share the same bucket.
+#[derive(Clone)]
+pub struct RateLimiter {
+    bucket: Arc<Mutex<Bucket>>
+}
+
+impl RateLimiter {
+
+    pub fn new(capacity: u32, window: Duration) -> Self {
+        let capacity = capacity.max(1) as f64;
+        Self {
+            bucket: Arc::new(Mutex::new(Bucket {
+                capacity,
+                tokens: capacity,
+                refill_per_sec: capacity / window.as_secs_f64(),
+                last_refill: Instant::now()
+            }))
+        }
+    }
+
+    /// Takes `weight` tokens, waiting for the bucket to refill if the budget is exhausted.
+    /// Weights above the capacity are clamped so a single request can never block forever.
+    pub async fn acquire(&self, weight: u32) {
+        let wait = {
+            let mut bucket = self.bucket.lock().unwrap();
+            let now = Instant::now();
+            bucket.refill(now);
+
+            let weight = (weight as f64).min(bucket.capacity);
+            bucket.tokens -= weight;
+
+            if bucket.tokens >= 0.0 {
+                None
+            } else {
+                // the deficit is reserved so concurrent callers queue up behind this one
+                Some(Duration::from_secs_f64(-bucket.tokens / bucket.refill_per_sec))
+            }
+        };
+
+        if let Some(wait) = wait {
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Currently available budget
+    pub fn remaining(&self) -> u32 {
+        let mut bucket = self.bucket.lock().unwrap();
+        bucket.refill(Instant::now());
+        bucket.tokens.max(0.0) as u32
+    }
+
+    pub fn capacity(&self) -> u32 {
+        self.bucket.lock().unwrap().capacity as u32
+    }
+}
+
+/// Request weight and order count budgets of a client
+#[derive(Clone)]
+pub struct RateLimits {
+    pub weight: RateLimiter,
+    pub orders: RateLimiter
+}
+
+impl RateLimits {
+
+    pub fn new(weight: RateLimiter, orders: RateLimiter) -> Self {
+        Self {
+            weight,
+            orders
+        }
+    }
+
+    pub fn spot() -> Self {
+        Self::new(
+            RateLimiter::new(SPOT_WEIGHT_PER_WINDOW, SPOT_WINDOW),
+            RateLimiter::new(SPOT_ORDERS_PER_WINDOW, SPOT_WINDOW)
+        )
+    }
+
+    pub fn futures() -> Self {
+        Self::new(
+            RateLimiter::new(FUTURES_REQUESTS_PER_WINDOW, FUTURES_WINDOW),
+            RateLimiter::new(FUTURES_REQUESTS_PER_WINDOW, FUTURES_WINDOW)
+        )
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    pub async fn test_acquire_waits_for_refill() {
+        let limiter = RateLimiter::new(4, Duration::from_millis(200));
+
+        let inst = std::time::Instant::now();
+        limiter.acquire(4).await;
+        assert_eq!(limiter.remaining(), 0);
+        assert!(inst.elapsed() < Duration::from_millis(20));
+
+        // 2 tokens refill in 100ms
+        limiter.acquire(2).await;
+        assert!(inst.elapsed() >= Duration::from_millis(90));
+    }
+
+    #[tokio::test]
+    pub async fn test_clones_share_budget() {
+        let limiter = RateLimiter::new(10, SPOT_WINDOW);
+        let shared = limiter.clone();
+
+        shared.acquire(3).await;
+        assert_eq!(limiter.remaining(), 7);
+    }
+
+    #[tokio::test]
+    pub async fn test_oversized_weight_is_clamped() {
+        let limiter = RateLimiter::new(10, SPOT_WINDOW);
+
+        let inst = std::time::Instant::now();
+        limiter.acquire(100).await;
+        assert_eq!(limiter.remaining(), 0);
+        assert!(inst.elapsed() < Duration::from_millis(20));
+    }
+
+    #[test]
+    pub fn test_depth_weight() {
+        assert_eq!(weights::depth(None), 1);
+        assert_eq!(weights::depth(Some(500)), 5);
+        assert_eq!(weights::depth(Some(5000)), 50);
+    }
+}
@@ -0,0 +1,130 @@
+use std::future::Future;
+use std::time::Duration;
+use rand::Rng;
+use crate::{MexcError, MexcResult};
+
+#[derive(Clone, Debug)]
+pub struct RetryPolicy {
+    /// Total number of attempts, including the first one
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+    /// Randomize each delay between zero and the exponential backoff ("full jitter")
+    pub jitter: bool,
+    /// Also retry order placement when the order carries a client order id, so a duplicate is rejected by the exchange
+    pub retry_with_client_order_id: bool
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+            jitter: true,
+            retry_with_client_order_id: false
+        }
+    }
+}
+
+impl RetryPolicy {
+
+    /// Single attempt, never retries
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exp = self.base_delay.saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)));
+        let delay = exp.min(self.max_delay);
+
+        if self.jitter {
+            delay.mul_f64(rand::thread_rng().gen_range(0.0..=1.0))
+        } else {
+            delay
+        }
+    }
+
+    /// Rate limits, server side failures and connection level errors are worth another attempt
+    pub fn is_retryable(err: &MexcError) -> bool {
+        match err {
+            MexcError::Transport(err) => err.is_connect() || err.is_timeout() || err.is_request(),
+            MexcError::Http { status, .. } => status.is_server_error() || err.is_rate_limited(),
+            MexcError::Api { .. } | MexcError::FuturesResponse { .. } => err.is_rate_limited(),
+            _ => false
+        }
+    }
+
+    /// Runs `request` until it succeeds, fails with a non retryable error or the attempts are used up.
+    /// `request` is invoked anew for each attempt so signed requests pick up a fresh timestamp.
+    pub(crate) async fn run<T, F, Fut>(&self, retryable: bool, mut request: F) -> MexcResult<T>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = MexcResult<T>>
+    {
+        let mut attempt = 1;
+        loop {
+            match request().await {
+                Err(err) if retryable && attempt < self.max_attempts && Self::is_retryable(&err) => {
+                    tokio::time::sleep(self.backoff(attempt)).await;
+                    attempt += 1;
+                },
+                res => return res
+            }
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::StatusCode;
+
+    #[test]
+    pub fn test_backoff_is_capped() {
+        let policy = RetryPolicy {
+            jitter: false,
+            ..Default::default()
+        };
+
+        assert_eq!(policy.backoff(1), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(800));
+        assert_eq!(policy.backoff(30), Duration::from_secs(5));
+        assert!(RetryPolicy::default().backoff(3) <= Duration::from_millis(800));
+    }
+
+    #[test]
+    pub fn test_is_retryable() {
+        assert!(RetryPolicy::is_retryable(&MexcError::Http { status: StatusCode::BAD_GATEWAY, body: String::new() }));
+        assert!(RetryPolicy::is_retryable(&MexcError::Http { status: StatusCode::TOO_MANY_REQUESTS, body: String::new() }));
+        assert!(!RetryPolicy::is_retryable(&MexcError::Http { status: StatusCode::BAD_REQUEST, body: String::new() }));
+        assert!(!RetryPolicy::is_retryable(&MexcError::Api { code: 10101, msg: "Insufficient balance".into() }));
+    }
+
+    #[tokio::test]
+    pub async fn test_run_stops_after_max_attempts() {
+        let policy = RetryPolicy {
+            base_delay: Duration::from_millis(1),
+            ..Default::default()
+        };
+
+        let mut calls = 0;
+        let res: MexcResult<()> = policy.run(true, || {
+            calls += 1;
+            async { Err(MexcError::Http { status: StatusCode::SERVICE_UNAVAILABLE, body: String::new() }) }
+        }).await;
+        assert!(res.is_err());
+        assert_eq!(calls, 3);
+
+        let mut calls = 0;
+        let _ = policy.run::<(), _, _>(false, || {
+            calls += 1;
+            async { Err(MexcError::Http { status: StatusCode::SERVICE_UNAVAILABLE, body: String::new() }) }
+        }).await;
+        assert_eq!(calls, 1);
+    }
+}
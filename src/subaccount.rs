@@ -0,0 +1,398 @@
+use std::fmt;
+use std::fmt::Write;
+use reqwest::Method;
+use serde::{Deserialize, Deserializer};
+use crate::{Mexc, MexcBuilder, MexcError, MexcResult, rate_limit::weights, utils::{encode, parse_string_to_f64}};
+use crate::account::AccountBalance;
+use crate::num::Numeric;
+use crate::orders::DEFAULT_RECV_WINDOW;
+use crate::wallet::{AccountType, TransferStatus};
+
+// https://mexcdevelop.github.io/apidocs/spot_v3_en/#create-an-apikey-for-a-sub-account-for-master-account
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SubAccountPermission {
+    SpotAccountRead,
+    SpotAccountWrite,
+    SpotDealRead,
+    SpotDealWrite,
+    ContractAccountRead,
+    ContractAccountWrite,
+    ContractDealRead,
+    ContractDealWrite,
+    SpotTransferRead,
+    SpotTransferWrite,
+    /// Listed by the exchange but not known to this crate
+    Unknown
+}
+
+impl SubAccountPermission {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SubAccountPermission::SpotAccountRead => "SPOT_ACCOUNT_READ",
+            SubAccountPermission::SpotAccountWrite => "SPOT_ACCOUNT_WRITE",
+            SubAccountPermission::SpotDealRead => "SPOT_DEAL_READ",
+            SubAccountPermission::SpotDealWrite => "SPOT_DEAL_WRITE",
+            SubAccountPermission::ContractAccountRead => "CONTRACT_ACCOUNT_READ",
+            SubAccountPermission::ContractAccountWrite => "CONTRACT_ACCOUNT_WRITE",
+            SubAccountPermission::ContractDealRead => "CONTRACT_DEAL_READ",
+            SubAccountPermission::ContractDealWrite => "CONTRACT_DEAL_WRITE",
+            SubAccountPermission::SpotTransferRead => "SPOT_TRANSFER_READ",
+            SubAccountPermission::SpotTransferWrite => "SPOT_TRANSFER_WRITE",
+            SubAccountPermission::Unknown => "UNKNOWN"
+        }
+    }
+}
+
+impl fmt::Display for SubAccountPermission {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<&str> for SubAccountPermission {
+    fn from(permission: &str) -> Self {
+        match permission.trim() {
+            "SPOT_ACCOUNT_READ" => SubAccountPermission::SpotAccountRead,
+            "SPOT_ACCOUNT_WRITE" => SubAccountPermission::SpotAccountWrite,
+            "SPOT_DEAL_READ" => SubAccountPermission::SpotDealRead,
+            "SPOT_DEAL_WRITE" => SubAccountPermission::SpotDealWrite,
+            "CONTRACT_ACCOUNT_READ" => SubAccountPermission::ContractAccountRead,
+            "CONTRACT_ACCOUNT_WRITE" => SubAccountPermission::ContractAccountWrite,
+            "CONTRACT_DEAL_READ" => SubAccountPermission::ContractDealRead,
+            "CONTRACT_DEAL_WRITE" => SubAccountPermission::ContractDealWrite,
+            "SPOT_TRANSFER_READ" => SubAccountPermission::SpotTransferRead,
+            "SPOT_TRANSFER_WRITE" => SubAccountPermission::SpotTransferWrite,
+            _ => SubAccountPermission::Unknown
+        }
+    }
+}
+
+/// Permissions come as one comma separated string
+fn parse_permissions<'de, D>(deserializer: D) -> Result<Vec<SubAccountPermission>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let permissions: Option<String> = Deserialize::deserialize(deserializer)?;
+    Ok(permissions
+        .unwrap_or_default()
+        .split(',')
+        .filter(|permission| !permission.trim().is_empty())
+        .map(SubAccountPermission::from)
+        .collect())
+}
+
+fn join_permissions(permissions: &[SubAccountPermission]) -> String {
+    permissions.iter().map(SubAccountPermission::as_str).collect::<Vec<_>>().join(",")
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SubAccount {
+    #[serde(rename = "subAccount")]
+    pub sub_account: String,
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(rename = "isFreeze", default)]
+    pub is_freeze: bool,
+    #[serde(rename = "createTime", default)]
+    pub create_time: Option<u128>,
+    #[serde(default)]
+    pub uid: Option<String>
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct SubAccountList {
+    #[serde(rename = "subAccounts")]
+    sub_accounts: Vec<SubAccount>
+}
+
+/// Api key of a sub-account, the secret is only returned when the key is created
+#[derive(Deserialize, Debug, Clone)]
+pub struct SubAccountApiKey {
+    #[serde(rename = "subAccount", default)]
+    pub sub_account: String,
+    #[serde(default)]
+    pub note: Option<String>,
+    #[serde(rename = "apiKey")]
+    pub api_key: String,
+    #[serde(rename = "secretKey", default)]
+    pub secret_key: Option<String>,
+    #[serde(deserialize_with = "parse_permissions")]
+    pub permissions: Vec<SubAccountPermission>,
+    #[serde(default)]
+    pub ip: Option<String>,
+    #[serde(rename = "createTime", alias = "creatTime", default)]
+    pub create_time: Option<u128>
+}
+
+impl SubAccountApiKey {
+
+    /// Client builder signing with this key, only possible right after it was created
+    pub fn builder(&self) -> MexcResult<MexcBuilder> {
+        let secret_key = self.secret_key.as_ref().ok_or(MexcError::MissingCredentials("secret key"))?;
+        Ok(Mexc::builder().api_key(self.api_key.clone()).api_secret(secret_key.clone()))
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct SubAccountApiKeys {
+    #[serde(rename = "subAccount")]
+    api_keys: Vec<SubAccountApiKey>
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct SubAccountName {
+    #[serde(rename = "subAccount")]
+    sub_account: String
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct SubAccountTransferId {
+    #[serde(rename = "tranId")]
+    tran_id: String
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SubAccountTransfer {
+    #[serde(rename = "tranId")]
+    pub tran_id: String,
+    #[serde(rename = "fromAccount", default)]
+    pub from_account: Option<String>,
+    #[serde(rename = "toAccount", default)]
+    pub to_account: Option<String>,
+    #[serde(rename = "clientTranId", default)]
+    pub client_tran_id: Option<String>,
+    pub asset: String,
+    #[serde(deserialize_with = "parse_string_to_f64")]
+    pub amount: f64,
+    #[serde(rename = "fromAccountType")]
+    pub from_account_type: AccountType,
+    #[serde(rename = "toAccountType")]
+    pub to_account_type: AccountType,
+    pub status: TransferStatus,
+    pub timestamp: u128
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct SubAccountTransferPage {
+    #[serde(rename = "result", default)]
+    pub transfers: Vec<SubAccountTransfer>,
+    pub total: u64
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct SubAccountAssets {
+    balances: Vec<AccountBalance>
+}
+
+/// One side of a universal transfer: a wallet of the master account (`account` unset) or of a sub-account
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransferAccount {
+    pub account: Option<String>,
+    pub account_type: AccountType
+}
+
+impl TransferAccount {
+    pub fn master(account_type: AccountType) -> Self {
+        Self { account: None, account_type }
+    }
+
+    pub fn sub(sub_account: impl Into<String>, account_type: AccountType) -> Self {
+        Self { account: Some(sub_account.into()), account_type }
+    }
+}
+
+/// Filters of the sub-account transfer history, the last 6 months by default
+#[derive(Debug, Clone, Default)]
+pub struct SubAccountTransferQuery {
+    pub from_account: Option<String>,
+    pub to_account: Option<String>,
+    pub start: Option<u128>,
+    pub end: Option<u128>,
+    /// default 1
+    pub page: Option<u32>,
+    /// default 500; max 500
+    pub limit: Option<u32>
+}
+
+impl Mexc {
+
+    /// Creates a virtual sub-account, returns its name
+    pub async fn create_sub_account(&self, sub_account: &str, note: &str, recv_window: Option<u64>) -> MexcResult<String> {
+        let recv_window = recv_window.unwrap_or(DEFAULT_RECV_WINDOW);
+
+        let request = format!("subAccount={}&note={}&recvWindow={recv_window}", encode(sub_account), encode(note));
+        let resp: SubAccountName = self.send_signed(Method::POST, "/api/v3/sub-account/virtualSubAccount", &request, weights::DEFAULT).await?;
+        Ok(resp.sub_account)
+    }
+
+    /// Sub-accounts of the master account. page: default 1; limit: default 10, max 200
+    pub async fn get_sub_accounts(&self, sub_account: Option<&str>, is_freeze: Option<bool>, page: Option<u32>, limit: Option<u32>, recv_window: Option<u64>) -> MexcResult<Vec<SubAccount>> {
+        let recv_window = recv_window.unwrap_or(DEFAULT_RECV_WINDOW);
+
+        let mut request = String::new();
+        if let Some(sub_account) = sub_account {
+            let _ = write!(request, "subAccount={}&", encode(sub_account));
+        }
+        if let Some(is_freeze) = is_freeze {
+            let _ = write!(request, "isFreeze={is_freeze}&");
+        }
+        if let Some(page) = page {
+            let _ = write!(request, "page={page}&");
+        }
+        if let Some(limit) = limit {
+            let _ = write!(request, "limit={limit}&");
+        }
+        let _ = write!(request, "recvWindow={recv_window}");
+        let resp: SubAccountList = self.send_signed(Method::GET, "/api/v3/sub-account/list", &request, weights::DEFAULT).await?;
+        Ok(resp.sub_accounts)
+    }
+
+    /// Creates an api key for `sub_account`, optionally bound to comma separated `ip` addresses
+    pub async fn create_sub_account_api_key(&self, sub_account: &str, note: &str, permissions: &[SubAccountPermission], ip: Option<&str>, recv_window: Option<u64>) -> MexcResult<SubAccountApiKey> {
+        let recv_window = recv_window.unwrap_or(DEFAULT_RECV_WINDOW);
+
+        let mut request = format!("subAccount={}&note={}&permissions={}", encode(sub_account), encode(note), encode(&join_permissions(permissions)));
+        if let Some(ip) = ip {
+            let _ = write!(request, "&ip={}", encode(ip));
+        }
+        let _ = write!(request, "&recvWindow={recv_window}");
+        self.send_signed(Method::POST, "/api/v3/sub-account/apiKey", &request, weights::DEFAULT).await
+    }
+
+    /// Api keys of `sub_account`, without their secrets
+    pub async fn get_sub_account_api_keys(&self, sub_account: &str, recv_window: Option<u64>) -> MexcResult<Vec<SubAccountApiKey>> {
+        let recv_window = recv_window.unwrap_or(DEFAULT_RECV_WINDOW);
+
+        let request = format!("subAccount={}&recvWindow={recv_window}", encode(sub_account));
+        let resp: SubAccountApiKeys = self.send_signed(Method::GET, "/api/v3/sub-account/apiKey", &request, weights::DEFAULT).await?;
+        Ok(resp.api_keys)
+    }
+
+    pub async fn delete_sub_account_api_key(&self, sub_account: &str, api_key: &str, recv_window: Option<u64>) -> MexcResult<String> {
+        let recv_window = recv_window.unwrap_or(DEFAULT_RECV_WINDOW);
+
+        let request = format!("subAccount={}&apiKey={}&recvWindow={recv_window}", encode(sub_account), encode(api_key));
+        let resp: SubAccountName = self.send_signed(Method::DELETE, "/api/v3/sub-account/apiKey", &request, weights::DEFAULT).await?;
+        Ok(resp.sub_account)
+    }
+
+    /// Moves `amount` of `asset` between master and sub-account wallets, returns the transfer id.
+    /// Only the master account's key may call this.
+    pub async fn sub_account_transfer<N: Numeric>(&self, from: &TransferAccount, to: &TransferAccount, asset: &str, amount: N, recv_window: Option<u64>) -> MexcResult<String> {
+        let recv_window = recv_window.unwrap_or(DEFAULT_RECV_WINDOW);
+
+        let mut request = String::new();
+        if let Some(account) = &from.account {
+            let _ = write!(request, "fromAccount={}&", encode(account));
+        }
+        if let Some(account) = &to.account {
+            let _ = write!(request, "toAccount={}&", encode(account));
+        }
+        let _ = write!(request, "fromAccountType={}&toAccountType={}&asset={}&amount={amount}&recvWindow={recv_window}", from.account_type, to.account_type, encode(asset));
+        let resp: SubAccountTransferId = self.send_signed(Method::POST, "/api/v3/capital/sub-account/universalTransfer", &request, weights::DEFAULT).await?;
+        Ok(resp.tran_id)
+    }
+
+    pub async fn get_sub_account_transfers(&self, from: AccountType, to: AccountType, query: &SubAccountTransferQuery, recv_window: Option<u64>) -> MexcResult<SubAccountTransferPage> {
+        let recv_window = recv_window.unwrap_or(DEFAULT_RECV_WINDOW);
+
+        let mut request = String::new();
+        if let Some(account) = &query.from_account {
+            let _ = write!(request, "fromAccount={}&", encode(account));
+        }
+        if let Some(account) = &query.to_account {
+            let _ = write!(request, "toAccount={}&", encode(account));
+        }
+        let _ = write!(request, "fromAccountType={from}&toAccountType={to}");
+        if let Some(start) = query.start {
+            let _ = write!(request, "&startTime={start}");
+        }
+        if let Some(end) = query.end {
+            let _ = write!(request, "&endTime={end}");
+        }
+        if let Some(page) = query.page {
+            let _ = write!(request, "&page={page}");
+        }
+        if let Some(limit) = query.limit {
+            let _ = write!(request, "&limit={limit}");
+        }
+        let _ = write!(request, "&recvWindow={recv_window}");
+        self.send_signed(Method::GET, "/api/v3/capital/sub-account/universalTransfer", &request, weights::DEFAULT).await
+    }
+
+    /// Balances of one wallet of `sub_account`
+    pub async fn get_sub_account_assets(&self, sub_account: &str, account_type: AccountType, recv_window: Option<u64>) -> MexcResult<Vec<AccountBalance>> {
+        let recv_window = recv_window.unwrap_or(DEFAULT_RECV_WINDOW);
+
+        let request = format!("subAccount={}&accountType={account_type}&recvWindow={recv_window}", encode(sub_account));
+        let resp: SubAccountAssets = self.send_signed(Method::GET, "/api/v3/sub-account/asset", &request, weights::DEFAULT).await?;
+        Ok(resp.balances)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::serve;
+
+    #[tokio::test]
+    pub async fn test_sub_accounts_and_keys() {
+        let (base_url, handle) = serve(vec![
+            (200, r#"{"subAccount":"strategy7","note":"mm"}"#),
+            (200, r#"{"subAccounts":[{"subAccount":"strategy7","isFreeze":false,"createTime":1544433328000,"uid":"49910594"}]}"#),
+            (200, r#"{"subAccount":"strategy7","note":"bot","apiKey":"mx0aBYs33eIilxBW","secretKey":"ca6a2b3a9a644f4c9bd93f2b7b8a0c24","permissions":"SPOT_DEAL_READ,SPOT_DEAL_WRITE","ip":"1.2.3.4","createTime":1597026383085}"#),
+            (200, r#"{"subAccount":[{"note":"bot","apiKey":"mx0aBYs33eIilxBW","permissions":"SPOT_DEAL_READ,SPOT_DEAL_WRITE,NEW_SCOPE","ip":"1.2.3.4","creatTime":1666527068000}]}"#),
+            (200, r#"{"subAccount":"strategy7"}"#)
+        ]).await;
+        let client = Mexc::builder().base_url(base_url).api_key("key").api_secret("secret").build().unwrap();
+
+        assert_eq!(client.create_sub_account("strategy7", "mm", None).await.unwrap(), "strategy7");
+        let accounts = client.get_sub_accounts(None, Some(false), None, Some(200), None).await.unwrap();
+        assert_eq!(accounts[0].uid.as_deref(), Some("49910594"));
+
+        let permissions = [SubAccountPermission::SpotDealRead, SubAccountPermission::SpotDealWrite];
+        let key = client.create_sub_account_api_key("strategy7", "bot", &permissions, Some("1.2.3.4"), None).await.unwrap();
+        assert_eq!(key.permissions, permissions);
+        assert!(key.builder().is_ok());
+
+        let keys = client.get_sub_account_api_keys("strategy7", None).await.unwrap();
+        assert_eq!(keys[0].create_time, Some(1666527068000));
+        assert_eq!(keys[0].permissions[2], SubAccountPermission::Unknown);
+        assert!(matches!(keys[0].builder(), Err(MexcError::MissingCredentials(_))));
+        assert_eq!(client.delete_sub_account_api_key("strategy7", &keys[0].api_key, None).await.unwrap(), "strategy7");
+
+        let requests = handle.await.unwrap();
+        assert!(requests[0].starts_with("POST /api/v3/sub-account/virtualSubAccount?subAccount=strategy7&note=mm&"));
+        assert!(requests[1].starts_with("GET /api/v3/sub-account/list?isFreeze=false&limit=200&recvWindow="));
+        assert!(requests[2].starts_with("POST /api/v3/sub-account/apiKey?subAccount=strategy7&note=bot&permissions=SPOT_DEAL_READ%2CSPOT_DEAL_WRITE&ip=1.2.3.4&"));
+        assert!(requests[4].starts_with("DELETE /api/v3/sub-account/apiKey?subAccount=strategy7&apiKey=mx0aBYs33eIilxBW&"));
+    }
+
+    #[tokio::test]
+    pub async fn test_sub_account_transfers_and_assets() {
+        let (base_url, handle) = serve(vec![
+            (200, r#"{"tranId":"11945860693"}"#),
+            (200, r#"{"total":1,"result":[{"tranId":"11945860693","fromAccount":"master@test.com","toAccount":"strategy7","clientTranId":null,"asset":"USDT","amount":"100","fromAccountType":"SPOT","toAccountType":"FUTURE","fromSymbol":"SPOT","toSymbol":"FUTURE","status":"SUCCESS","timestamp":1544433325000}]}"#),
+            (200, r#"{"balances":[{"asset":"USDT","free":"100","locked":"0"}]}"#)
+        ]).await;
+        let client = Mexc::builder().base_url(base_url).api_key("key").api_secret("secret").build().unwrap();
+
+        let from = TransferAccount::master(AccountType::SPOT);
+        let to = TransferAccount::sub("strategy7", AccountType::FUTURES);
+        assert_eq!(client.sub_account_transfer(&from, &to, "USDT", 100.0, None).await.unwrap(), "11945860693");
+
+        let query = SubAccountTransferQuery { to_account: Some("strategy7".into()), ..Default::default() };
+        let page = client.get_sub_account_transfers(AccountType::SPOT, AccountType::FUTURES, &query, None).await.unwrap();
+        assert_eq!(page.transfers[0].to_account_type, AccountType::FUTURES);
+        assert_eq!(page.transfers[0].status, TransferStatus::Success);
+
+        let balances = client.get_sub_account_assets("strategy7", AccountType::FUTURES, None).await.unwrap();
+        assert_eq!(balances[0].free, 100.0);
+
+        let requests = handle.await.unwrap();
+        assert!(requests[0].starts_with("POST /api/v3/capital/sub-account/universalTransfer?toAccount=strategy7&fromAccountType=SPOT&toAccountType=FUTURES&asset=USDT&amount=100&"));
+        assert!(requests[1].starts_with("GET /api/v3/capital/sub-account/universalTransfer?toAccount=strategy7&fromAccountType=SPOT&toAccountType=FUTURES&recvWindow="));
+        assert!(requests[2].starts_with("GET /api/v3/sub-account/asset?subAccount=strategy7&accountType=FUTURES&"));
+    }
+}
@@ -0,0 +1,273 @@
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use crate::{Mexc, MexcError, MexcResult};
+use crate::market::{ExchangeInfo, SymbolInfo};
+use crate::orders::{OrderRequest, OrderType};
+
+// floating point slack when checking amounts against a tick or step
+const STEP_TOLERANCE: f64 = 1e-9;
+
+fn decimals_of(step: f64) -> u32 {
+    let step = step.to_string();
+    step.split_once('.').map_or(0, |(_, fraction)| fraction.len() as u32)
+}
+
+fn round_decimals(x: f64, decimals: u32) -> f64 {
+    let y = 10f64.powi(decimals as i32);
+    (x * y).round() / y
+}
+
+fn floor_to_step(x: f64, step: f64) -> f64 {
+    round_decimals((x / step + STEP_TOLERANCE).floor() * step, decimals_of(step))
+}
+
+fn is_on_step(x: f64, step: f64) -> bool {
+    let steps = x / step;
+    (steps - steps.round()).abs() < STEP_TOLERANCE * steps.abs().max(1.0)
+}
+
+impl SymbolInfo {
+
+    /// Open for spot trading
+    pub fn is_trading(&self) -> bool {
+        self.is_spot_trading_allowed && (self.status == "1" || self.status == "ENABLED")
+    }
+
+    /// Smallest price increment, from `quotePrecision`
+    pub fn price_tick(&self) -> f64 {
+        10f64.powi(-(self.quote_precision as i32))
+    }
+
+    /// Smallest quantity increment, `baseSizePrecision` or `baseAssetPrecision` decimals when that is zero
+    pub fn quantity_step(&self) -> f64 {
+        if self.base_size_precision > 0.0 {
+            self.base_size_precision
+        } else {
+            10f64.powi(-(self.base_asset_precision as i32))
+        }
+    }
+
+    /// Nearest price on the tick
+    pub fn round_price(&self, price: f64) -> f64 {
+        let tick = self.price_tick();
+        round_decimals((price / tick).round() * tick, self.quote_precision)
+    }
+
+    /// Quantity floored to the step, so it never exceeds what was asked for
+    pub fn round_quantity(&self, quantity: f64) -> f64 {
+        floor_to_step(quantity, self.quantity_step())
+    }
+
+    /// Quote amount floored to `quoteAssetPrecision` decimals
+    pub fn round_quote_amount(&self, amount: f64) -> f64 {
+        floor_to_step(amount, 10f64.powi(-(self.quote_asset_precision as i32)))
+    }
+
+    pub fn supports(&self, order_type: OrderType) -> bool {
+        let order_type = order_type.to_string();
+        self.order_types.contains(&order_type)
+    }
+
+    /// Checks `order` against the symbol's rules: trading status, order type, tick and step, minimum
+    /// and maximum quote amount. Market orders by base quantity have no price to check the notional with.
+    pub fn validate(&self, order: &OrderRequest) -> MexcResult<()> {
+        let invalid = |reason: String| Err(MexcError::InvalidRequest(format!("{}: {reason}", self.symbol)));
+
+        if order.symbol() != self.symbol {
+            return invalid(format!("order is for {}", order.symbol()));
+        }
+        if !self.is_trading() {
+            return invalid("not open for spot trading".into());
+        }
+        let order_type = order.order_type();
+        if !self.supports(order_type) {
+            return invalid(format!("{order_type} orders are not supported"));
+        }
+
+        if let Some(price) = order.price() {
+            if !is_on_step(price, self.price_tick()) {
+                return invalid(format!("price {price} is not a multiple of {}", self.price_tick()));
+            }
+        }
+        if let Some(quantity) = order.quantity() {
+            if !is_on_step(quantity, self.quantity_step()) {
+                return invalid(format!("quantity {quantity} is not a multiple of {}", self.quantity_step()));
+            }
+        }
+
+        let (notional, min, max) = match (order.price(), order.quantity(), order.quote_order_qty()) {
+            (_, _, Some(quote_order_qty)) => (quote_order_qty, self.quote_amount_precision_market, self.max_quote_amount_market),
+            (Some(price), Some(quantity), _) => (price * quantity, self.quote_amount_precision, self.max_quote_amount),
+            _ => return Ok(())
+        };
+        if notional < min {
+            return invalid(format!("order amount {notional} is below the minimum of {min} {}", self.quote_asset));
+        }
+        if max > 0.0 && notional > max {
+            return invalid(format!("order amount {notional} is above the maximum of {max} {}", self.quote_asset));
+        }
+        Ok(())
+    }
+
+    /// `order` with its price rounded to the tick and its amounts floored to the step, then validated.
+    /// Amounts already on the tick or step are kept as given, a `Decimal` one keeps its exact text.
+    pub fn prepare(&self, order: &OrderRequest) -> MexcResult<OrderRequest> {
+        let quote_step = 10f64.powi(-(self.quote_asset_precision as i32));
+        let rounded = order.map_amounts(
+            |price| (!is_on_step(price, self.price_tick())).then(|| self.round_price(price)),
+            |quantity| (!is_on_step(quantity, self.quantity_step())).then(|| self.round_quantity(quantity)),
+            |quote_order_qty| (!is_on_step(quote_order_qty, quote_step)).then(|| self.round_quote_amount(quote_order_qty))
+        )?;
+
+        self.validate(&rounded)?;
+        Ok(rounded)
+    }
+}
+
+/// Symbol rules from `exchange_info`, shared between tasks and refreshed in place
+#[derive(Debug, Default)]
+pub struct SymbolRegistry {
+    symbols: RwLock<HashMap<String, Arc<SymbolInfo>>>
+}
+
+impl SymbolRegistry {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn from_exchange_info(info: ExchangeInfo) -> Self {
+        let registry = Self::new();
+        registry.update(info);
+        registry
+    }
+
+    pub async fn load(client: &Mexc) -> MexcResult<Self> {
+        Ok(Self::from_exchange_info(client.exchange_info().await?))
+    }
+
+    /// Reloads every symbol, returns how many are known
+    pub async fn refresh(&self, client: &Mexc) -> MexcResult<usize> {
+        let info = client.exchange_info().await?;
+        Ok(self.update(info))
+    }
+
+    /// Replaces the known symbols with those in `info`, symbols no longer listed are dropped
+    pub fn update(&self, info: ExchangeInfo) -> usize {
+        let symbols: HashMap<_, _> = info.symbols
+            .into_iter()
+            .map(|symbol| (symbol.symbol.clone(), Arc::new(symbol)))
+            .collect();
+        let count = symbols.len();
+        *self.symbols.write().unwrap() = symbols;
+        count
+    }
+
+    pub fn get(&self, symbol: &str) -> Option<Arc<SymbolInfo>> {
+        self.symbols.read().unwrap().get(symbol).cloned()
+    }
+
+    /// Like `get`, unknown symbols are an error
+    pub fn info(&self, symbol: &str) -> MexcResult<Arc<SymbolInfo>> {
+        self.get(symbol).ok_or_else(|| MexcError::InvalidRequest(format!("unknown symbol {symbol}")))
+    }
+
+    pub fn len(&self) -> usize {
+        self.symbols.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn round_price(&self, symbol: &str, price: f64) -> MexcResult<f64> {
+        Ok(self.info(symbol)?.round_price(price))
+    }
+
+    pub fn round_quantity(&self, symbol: &str, quantity: f64) -> MexcResult<f64> {
+        Ok(self.info(symbol)?.round_quantity(quantity))
+    }
+
+    pub fn validate(&self, order: &OrderRequest) -> MexcResult<()> {
+        self.info(order.symbol())?.validate(order)
+    }
+
+    /// Rounds and validates `order` against its symbol, see `SymbolInfo::prepare`
+    pub fn prepare(&self, order: &OrderRequest) -> MexcResult<OrderRequest> {
+        self.info(order.symbol())?.prepare(order)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orders::OrderSide;
+    use crate::testing::serve;
+
+    const EXCHANGE_INFO: &str = r#"{"timezone":"CST","serverTime":1710000000000,"symbols":[
+        {"symbol":"MXUSDT","status":"1","baseAsset":"MX","baseAssetPrecision":2,"quoteAsset":"USDT","quotePrecision":4,"quoteAssetPrecision":4,"baseCommissionPrecision":2,"quoteCommissionPrecision":4,"orderTypes":["LIMIT","MARKET","LIMIT_MAKER"],"isSpotTradingAllowed":true,"isMarginTradingAllowed":false,"quoteAmountPrecision":"5","baseSizePrecision":"0.01","permissions":["SPOT"],"filters":[],"maxQuoteAmount":"2000000","makerCommission":"0","takerCommission":"0.0005","quoteAmountPrecisionMarket":"5","maxQuoteAmountMarket":"100000","fullName":"MX Token"},
+        {"symbol":"PLSUSDT","status":"2","baseAsset":"PLS","baseAssetPrecision":0,"quoteAsset":"USDT","quotePrecision":8,"quoteAssetPrecision":8,"baseCommissionPrecision":0,"quoteCommissionPrecision":8,"orderTypes":["LIMIT","MARKET"],"isSpotTradingAllowed":true,"isMarginTradingAllowed":false,"quoteAmountPrecision":"1","baseSizePrecision":"0","permissions":["SPOT"],"filters":[],"maxQuoteAmount":"0","makerCommission":"0","takerCommission":"0","quoteAmountPrecisionMarket":"1","maxQuoteAmountMarket":"0","fullName":"PulseChain"}
+    ]}"#;
+
+    #[test]
+    pub fn test_rounding() {
+        let registry = SymbolRegistry::from_exchange_info(serde_json::from_str(EXCHANGE_INFO).unwrap());
+        let mx = registry.info("MXUSDT").unwrap();
+
+        assert_eq!(mx.price_tick(), 0.0001);
+        assert_eq!(mx.round_price(2.41237), 2.4124);
+        assert_eq!(mx.round_quantity(10.239), 10.23);
+        assert_eq!(mx.round_quantity(0.3), 0.3);
+        assert_eq!(registry.round_quantity("PLSUSDT", 1234.9).unwrap(), 1234.0);
+        assert!(registry.round_price("BTCUSDT", 1.0).is_err());
+    }
+
+    #[test]
+    pub fn test_validation() {
+        let registry = SymbolRegistry::from_exchange_info(serde_json::from_str(EXCHANGE_INFO).unwrap());
+
+        let order = OrderRequest::limit("MXUSDT", OrderSide::BUY, 2.41237, 10.239).unwrap().with_client_order_id("mine");
+        assert!(registry.validate(&order).is_err());
+        let prepared = registry.prepare(&order).unwrap();
+        assert_eq!(prepared.price(), Some(2.4124));
+        assert_eq!(prepared.quantity(), Some(10.23));
+        assert_eq!(prepared.client_order_id(), Some("mine"));
+
+        // below the 5 USDT minimum, above the market maximum, unsupported type, symbol paused
+        assert!(registry.validate(&OrderRequest::limit("MXUSDT", OrderSide::BUY, 2.5, 1.0).unwrap()).is_err());
+        assert!(registry.validate(&OrderRequest::market_quote("MXUSDT", OrderSide::BUY, 200000.0).unwrap()).is_err());
+        assert!(registry.validate(&OrderRequest::market_quote("MXUSDT", OrderSide::BUY, 50.0).unwrap()).is_ok());
+        assert!(registry.validate(&OrderRequest::fill_or_kill("MXUSDT", OrderSide::BUY, 2.5, 10.0).unwrap()).is_err());
+        assert!(registry.validate(&OrderRequest::market("PLSUSDT", OrderSide::SELL, 1000.0).unwrap()).is_err());
+    }
+
+    #[cfg(feature = "decimal")]
+    #[test]
+    pub fn test_prepare_keeps_decimals() {
+        use std::str::FromStr;
+        use crate::num::Decimal;
+
+        let registry = SymbolRegistry::from_exchange_info(serde_json::from_str(EXCHANGE_INFO).unwrap());
+        let decimal = |s: &str| Decimal::from_str(s).unwrap();
+
+        // on the tick and step already, sent as given
+        let order = OrderRequest::limit("MXUSDT", OrderSide::BUY, decimal("2.4100"), decimal("10.20")).unwrap();
+        assert!(registry.prepare(&order).unwrap().to_query().contains("&quantity=10.20&price=2.4100"));
+
+        let order = OrderRequest::limit("MXUSDT", OrderSide::BUY, decimal("2.41237"), decimal("10.20")).unwrap();
+        assert!(registry.prepare(&order).unwrap().to_query().contains("&quantity=10.20&price=2.4124"));
+    }
+
+    #[tokio::test]
+    pub async fn test_refresh() {
+        let (base_url, handle) = serve(vec![(200, EXCHANGE_INFO), (200, r#"{"serverTime":1710000000000,"symbols":[]}"#)]).await;
+        let client = Mexc::builder().base_url(base_url).build().unwrap();
+
+        let registry = SymbolRegistry::load(&client).await.unwrap();
+        assert_eq!(registry.len(), 2);
+        assert_eq!(registry.refresh(&client).await.unwrap(), 0);
+        assert!(registry.get("MXUSDT").is_none());
+        assert!(handle.await.unwrap()[0].starts_with("GET /api/v3/exchangeInfo"));
+    }
+}
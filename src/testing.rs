@@ -1,20 +1,537 @@
+// Answers one http request per response in order, returns the base url and the captured request lines
+#[cfg(test)]
+pub(crate) async fn serve<B>(responses: Vec<(u16, B)>) -> (String, tokio::task::JoinHandle<Vec<String>>)
+where
+    B: Into<String> + Send + 'static
+{
+    let mut responses = responses.into_iter();
+    serve_with(responses.len(), move |_| {
+        let (status, body) = responses.next().unwrap();
+        (status, body.into())
+    }).await
+}
+
+// Like `serve`, answering `count` requests with whatever `respond` makes of the request line
+#[cfg(test)]
+pub(crate) async fn serve_with<F>(count: usize, mut respond: F) -> (String, tokio::task::JoinHandle<Vec<String>>)
+where
+    F: FnMut(&str) -> (u16, String) + Send + 'static
+{
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base_url = format!("http://{}", listener.local_addr().unwrap());
+
+    let handle = tokio::spawn(async move {
+        let mut requests = vec![];
+        for _ in 0..count {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut buf = vec![0u8; 8192];
+            let n = socket.read(&mut buf).await.unwrap();
+            let request = String::from_utf8_lossy(&buf[..n]).lines().next().unwrap_or_default().to_string();
+            let (status, body) = respond(&request);
+            let response = format!("HTTP/1.1 {status} X\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}", body.len());
+            socket.write_all(response.as_bytes()).await.unwrap();
+            requests.push(request);
+        }
+        requests
+    });
+
+    (base_url, handle)
+}
 
 
 #[cfg(test)]
 mod tests {
     use std::time::Duration;
+    use crate::market::KlineInterval;
     use crate::orders::CancelledOrder;
     use crate::orders::Order;
     use crate::orders::OrderSide;
     use crate::orders::OrderType;
+    use crate::orders::{OrderRequest, MAX_BATCH_ORDERS};
+    use crate::orders::{OrderReplace, Replacement};
     use crate::orders::OrderReceipt;
+    use crate::orders::{OrderRef, OrderStatus};
     use crate::utils::round;
-    use crate::{utils::unlock_keys, Mexc};
+    use crate::retry::RetryPolicy;
+    use crate::{utils::unlock_keys, Mexc, MexcError};
+    use super::{serve, serve_with};
 
     async fn sleep(secs: f64) {
         tokio::time::sleep(Duration::from_secs_f64(secs)).await;
     }
 
+    #[tokio::test]
+    pub async fn test_builder_base_url() {
+        let (base_url, handle) = serve(vec![(200, r#"{"serverTime":1717363075282}"#)]).await;
+        let client = Mexc::builder().base_url(format!("{base_url}/")).build().unwrap();
+
+        let time = client.get_server_time().await.unwrap();
+        assert_eq!(time, 1717363075282);
+        assert_eq!(handle.await.unwrap(), vec!["GET /api/v3/time HTTP/1.1"]);
+    }
+
+    #[tokio::test]
+    pub async fn test_retry_resigns_request() {
+        let (base_url, handle) = serve(vec![
+            (503, "Service Unavailable"),
+            (200, r#"{"accountType":"SPOT","canDeposit":true,"canTrade":true,"canWithdraw":true,"permissions":["SPOT"],"balances":[]}"#)
+        ]).await;
+
+        let client = Mexc::builder()
+            .base_url(base_url)
+            .api_key("key")
+            .api_secret("secret")
+            .retry_policy(RetryPolicy { base_delay: Duration::from_millis(5), jitter: false, ..Default::default() })
+            .build().unwrap();
+
+        let acc = client.get_account().await.unwrap();
+        assert_eq!(acc.account_type, "SPOT");
+
+        let requests = handle.await.unwrap();
+        assert_eq!(requests.len(), 2);
+        assert_ne!(requests[0], requests[1]);
+    }
+
+    #[tokio::test]
+    pub async fn test_no_retry_on_order_placement() {
+        let (base_url, handle) = serve(vec![(503, "Service Unavailable")]).await;
+
+        let client = Mexc::builder()
+            .base_url(base_url)
+            .api_key("key")
+            .api_secret("secret")
+            .build().unwrap();
+
+        let err = client.submit_order("PLSUSDT", OrderSide::SELL, OrderType::LIMIT, 0.00009512, 599971.13, None).await.unwrap_err();
+        assert!(matches!(err, MexcError::Http { status, .. } if status.as_u16() == 503));
+        assert_eq!(handle.await.unwrap().len(), 1);
+    }
+
+
+    #[tokio::test]
+    pub async fn test_klines_pagination() {
+        let (base_url, handle) = serve(vec![
+            (200, r#"[[1000,"1.0","1.2","0.9","1.1","10.5",1059,"11.55"],[1060,"1.1","1.3","1.0","1.2","4",1119,"4.8"]]"#),
+            // numbers are read as well as strings
+            (200, r#"[[1120,1.2,1.2,1.2,1.25,0,1179,0]]"#)
+        ]).await;
+        let client = Mexc::builder().base_url(base_url).build().unwrap();
+
+        let klines = client.get_klines_paged("PLSUSDT", KlineInterval::Min1, 1000, 5000, 2).await.unwrap();
+        assert_eq!(klines.len(), 3);
+        assert_eq!(klines[0].high, 1.2);
+        assert_eq!(klines[0].quote_volume, 11.55);
+        assert_eq!(klines[2].open_time, 1120);
+        assert_eq!(klines[2].close, 1.25);
+
+        let requests = handle.await.unwrap();
+        assert!(requests[0].contains("interval=1m&startTime=1000&endTime=5000&limit=2"));
+        assert!(requests[1].contains("startTime=1061&endTime=5000"));
+    }
+
+    #[tokio::test]
+    pub async fn test_agg_trades_pagination() {
+        // no ids disclosed: the second page restarts at t=2 and skips the trade at t=2 already seen
+        let (base_url, handle) = serve(vec![
+            (200, r#"[{"a":null,"f":null,"l":null,"p":"1.0","q":"5","T":1,"m":false,"M":true},{"a":null,"f":null,"l":null,"p":"1.1","q":"6","T":2,"m":true,"M":true}]"#),
+            (200, r#"[{"a":null,"f":null,"l":null,"p":"1.1","q":"6","T":2,"m":true,"M":true},{"a":null,"f":null,"l":null,"p":"1.2","q":"7","T":3,"m":false,"M":true}]"#),
+            (200, r#"[{"a":null,"f":null,"l":null,"p":"1.2","q":"7","T":3,"m":false,"M":true}]"#)
+        ]).await;
+        let client = Mexc::builder().base_url(base_url).build().unwrap();
+
+        let trades = client.get_agg_trades_paged("PLSUSDT", 1, 10, 2).await.unwrap();
+        assert_eq!(trades.iter().map(|trade| trade.qty).collect::<Vec<_>>(), vec![5.0, 6.0, 7.0]);
+        assert!(trades[1].is_buyer_maker);
+
+        let requests = handle.await.unwrap();
+        assert!(requests[0].contains("symbol=PLSUSDT&startTime=1&endTime=10&limit=2"));
+        assert!(requests[1].contains("startTime=2&endTime=10"));
+        assert!(requests[2].contains("startTime=3&endTime=10"));
+    }
+
+    #[tokio::test]
+    pub async fn test_agg_trades_pagination_by_id() {
+        let (base_url, handle) = serve(vec![
+            (200, r#"[{"a":10,"f":100,"l":101,"p":"1.0","q":"5","T":1,"m":false,"M":true},{"a":11,"f":102,"l":102,"p":"1.1","q":"6","T":2,"m":true,"M":true}]"#),
+            (200, r#"[{"a":12,"f":103,"l":103,"p":"1.2","q":"7","T":3,"m":false,"M":true},{"a":13,"f":104,"l":104,"p":"1.3","q":"8","T":11,"m":false,"M":true}]"#)
+        ]).await;
+        let client = Mexc::builder().base_url(base_url).build().unwrap();
+
+        let trades = client.get_agg_trades_paged("PLSUSDT", 1, 10, 2).await.unwrap();
+        assert_eq!(trades.iter().map(|trade| trade.id.unwrap()).collect::<Vec<_>>(), vec![10, 11, 12]);
+
+        let requests = handle.await.unwrap();
+        assert!(requests[1].contains("fromId=12&limit=2"));
+    }
+
+    #[tokio::test]
+    pub async fn test_agg_trades_pagination_same_millisecond() {
+        let (base_url, handle) = serve(vec![
+            (200, r#"[{"a":null,"f":null,"l":null,"p":"1.0","q":"5","T":1,"m":false,"M":true},{"a":null,"f":null,"l":null,"p":"1.1","q":"6","T":2,"m":true,"M":true}]"#),
+            (200, r#"[{"a":null,"f":null,"l":null,"p":"1.1","q":"6","T":2,"m":true,"M":true},{"a":null,"f":null,"l":null,"p":"1.2","q":"7","T":2,"m":false,"M":true}]"#)
+        ]).await;
+        let client = Mexc::builder().base_url(base_url).build().unwrap();
+
+        // a full page of trades at t=2 may hide more at t=2
+        let err = client.get_agg_trades_paged("PLSUSDT", 1, 10, 2).await.unwrap_err();
+        assert!(matches!(err, MexcError::InvalidRequest(_)));
+        assert_eq!(handle.await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    pub async fn test_tickers() {
+        let (base_url, handle) = serve(vec![
+            (200, r#"[{"symbol":"BTCUSDT","priceChange":"184.34","priceChangePercent":"0.00400048","prevClosePrice":"46079.37","lastPrice":"46263.71","lastQty":"","bidPrice":"46260.38","bidQty":"","askPrice":"46260.41","askQty":"","openPrice":"46079.37","highPrice":"47550.01","lowPrice":"45555.5","volume":"1732.461487","quoteVolume":null,"openTime":1641349500000,"closeTime":1641349582808,"count":null}]"#),
+            (200, r#"{"symbol":"AEUSDT","bidPrice":"0.11001","bidQty":"115.59","askPrice":"0.11127","askQty":"215.48"}"#),
+            (200, r#"{"mins":5,"price":"9.35751834"}"#)
+        ]).await;
+        let client = Mexc::builder().base_url(base_url).build().unwrap();
+
+        let tickers = client.get_tickers_24hr().await.unwrap();
+        assert_eq!(tickers[0].last_price, 46263.71);
+        assert_eq!(tickers[0].bid_price, Some(46260.38));
+        assert_eq!(tickers[0].bid_qty, None);
+        assert_eq!(tickers[0].quote_volume, None);
+
+        let ticker = client.get_book_ticker("AEUSDT").await.unwrap();
+        assert_eq!(ticker.ask_qty, 215.48);
+
+        let avg = client.get_avg_price("AEUSDT").await.unwrap();
+        assert_eq!(avg.mins, 5);
+
+        let requests = handle.await.unwrap();
+        assert_eq!(requests[0], "GET /api/v3/ticker/24hr HTTP/1.1");
+        assert_eq!(requests[1], "GET /api/v3/ticker/bookTicker?symbol=AEUSDT HTTP/1.1");
+        assert_eq!(requests[2], "GET /api/v3/avgPrice?symbol=AEUSDT HTTP/1.1");
+    }
+
+    fn order_json(order_id: &str, time: u64) -> String {
+        format!(r#"{{"symbol":"PLSUSDT","orderId":"{order_id}","orderListId":-1,"clientOrderId":"c-{order_id}","price":"0.0001","origQty":"100","executedQty":"40","cummulativeQuoteQty":"0.004","status":"PARTIALLY_FILLED","timeInForce":null,"type":"LIMIT","side":"BUY","stopPrice":null,"icebergQty":null,"time":{time},"updateTime":null,"isWorking":true,"origQuoteOrderQty":"0.01"}}"#)
+    }
+
+    #[tokio::test]
+    pub async fn test_get_order_by_client_id() {
+        let (base_url, handle) = serve(vec![(200, order_json("C02__1", 10))]).await;
+        let client = Mexc::builder().base_url(base_url).api_key("key").api_secret("secret").build().unwrap();
+
+        let order = client.get_order("PLSUSDT", OrderRef::client_id("c-C02__1"), None).await.unwrap();
+        assert_eq!(order.status, OrderStatus::PARTIALLY_FILLED);
+        assert_eq!(order.client_order_id.as_deref(), Some("c-C02__1"));
+        assert_eq!(order.cum_quote_qty, 0.004);
+        assert!(order.is_working);
+
+        let requests = handle.await.unwrap();
+        assert!(requests[0].starts_with("GET /api/v3/order?symbol=PLSUSDT&origClientOrderId=c-C02__1&recvWindow=5000&timestamp="));
+    }
+
+    #[tokio::test]
+    pub async fn test_all_orders_pagination() {
+        let day: u128 = 24 * 60 * 60 * 1000;
+        let (base_url, handle) = serve(vec![
+            (200, format!("[{},{}]", order_json("1", 10), order_json("2", 20))),
+            (200, format!("[{},{}]", order_json("2", 20), order_json("3", 30))),
+            (200, format!("[{}]", order_json("3", 30))),
+            (200, "[]".to_string())
+        ]).await;
+        let client = Mexc::builder().base_url(base_url).api_key("key").api_secret("secret").build().unwrap();
+
+        let orders = client.get_all_orders_paged("PLSUSDT", 0, 8 * day, 2).await.unwrap();
+        assert_eq!(orders.iter().map(|order| order.order_id.as_str()).collect::<Vec<_>>(), vec!["1", "2", "3"]);
+
+        let requests = handle.await.unwrap();
+        assert!(requests[0].contains(&format!("startTime=0&endTime={}&limit=2", 7 * day - 1)));
+        assert!(requests[1].contains("startTime=20&"));
+        assert!(requests[2].contains("startTime=30&"));
+        // the window follows the cursor
+        assert!(requests[2].contains(&format!("startTime=30&endTime={}", 7 * day + 29)));
+        assert!(requests[3].contains(&format!("startTime={}&endTime={}", 7 * day + 30, 8 * day)));
+    }
+
+    #[tokio::test]
+    pub async fn test_all_orders_pagination_same_millisecond() {
+        let (base_url, handle) = serve(vec![
+            (200, format!("[{},{}]", order_json("1", 10), order_json("2", 20))),
+            (200, format!("[{},{}]", order_json("2", 20), order_json("3", 20)))
+        ]).await;
+        let client = Mexc::builder().base_url(base_url).api_key("key").api_secret("secret").build().unwrap();
+
+        // a full page of orders at t=20 may hide more at t=20
+        let err = client.get_all_orders_paged("PLSUSDT", 0, 100, 2).await.unwrap_err();
+        assert!(matches!(err, MexcError::InvalidRequest(_)));
+        assert_eq!(handle.await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    pub async fn test_retry_placement_with_client_order_id() {
+        let (base_url, handle) = serve(vec![
+            (503, "Service Unavailable"),
+            (200, r#"{"symbol":"PLSUSDT","orderId":"C02__1","orderListId":-1,"clientOrderId":"my-order-1","price":"0.00009512","origQty":"599971.13","type":"LIMIT","side":"SELL","transactTime":1717363075282}"#)
+        ]).await;
+
+        let client = Mexc::builder()
+            .base_url(base_url)
+            .api_key("key")
+            .api_secret("secret")
+            .retry_policy(RetryPolicy { base_delay: Duration::from_millis(5), jitter: false, retry_with_client_order_id: true, ..Default::default() })
+            .build().unwrap();
+
+        let receipt = client.submit_order_with_client_id("PLSUSDT", OrderSide::SELL, OrderType::LIMIT, 0.00009512, 599971.13, "my-order-1", None).await.unwrap();
+        assert_eq!(receipt.client_order_id.as_deref(), Some("my-order-1"));
+
+        let requests = handle.await.unwrap();
+        assert_eq!(requests.len(), 2);
+        assert!(requests.iter().all(|request| request.contains("newClientOrderId=my-order-1&")));
+    }
+
+    #[tokio::test]
+    pub async fn test_cancel_by_client_order_id() {
+        let (base_url, handle) = serve(vec![
+            (200, r#"{"symbol":"PLSUSDT","origClientOrderId":"my-order-1","orderId":"C02__1","clientOrderId":"cancel-1","price":"0.00009512","origQty":"599971.13","executedQty":"0","cummulativeQuoteQty":"0","status":"CANCELED","timeInForce":"GTC","type":"LIMIT","side":"SELL"}"#),
+            (200, r#"{"symbol":"PLSUSDT","origClientOrderId":"my order&x=1","orderId":"C02__2","clientOrderId":"cancel-2","price":"0.00009512","origQty":"599971.13","executedQty":"0","cummulativeQuoteQty":"0","status":"CANCELED","timeInForce":"GTC","type":"LIMIT","side":"SELL"}"#)
+        ]).await;
+        let client = Mexc::builder().base_url(base_url).api_key("key").api_secret("secret").build().unwrap();
+
+        let cancelled = client.cancel_order("PLSUSDT", OrderRef::client_id("my-order-1"), None).await.unwrap();
+        assert_eq!(cancelled.orig_client_order_id.as_deref(), Some("my-order-1"));
+        client.cancel_order("PLSUSDT", OrderRef::client_id("my order&x=1"), None).await.unwrap();
+
+        let requests = handle.await.unwrap();
+        assert!(requests[0].starts_with("DELETE /api/v3/order?symbol=PLSUSDT&origClientOrderId=my-order-1&"));
+        assert!(requests[1].starts_with("DELETE /api/v3/order?symbol=PLSUSDT&origClientOrderId=my+order%26x%3D1&"));
+    }
+
+    #[tokio::test]
+    pub async fn test_batch_generates_client_order_ids() {
+        let (base_url, handle) = serve(vec![(200, "[]")]).await;
+        let client = Mexc::builder().base_url(base_url).api_key("key").api_secret("secret").build().unwrap();
+
+        let orders = vec![
+            Order::new("PLSUSDT", OrderSide::SELL, OrderType::LIMIT, 0.00009512, 100.0),
+            Order::new("PLSUSDT", OrderSide::SELL, OrderType::LIMIT, 0.00009712, 100.0).with_client_order_id("mine")
+        ];
+        client.batch_orders(orders, None).await.unwrap();
+
+        let requests = handle.await.unwrap();
+        assert_eq!(requests[0].matches("newClientOrderId").count(), 2);
+        assert!(requests[0].contains("newClientOrderId%22%3A%22mine%22"));
+
+        // one bad entry fails the whole batch before anything is sent
+        for quantity in [0.0, -100.0, f64::NAN] {
+            let orders = vec![
+                Order::new("PLSUSDT", OrderSide::SELL, OrderType::LIMIT, 0.00009512, 100.0),
+                Order::new("PLSUSDT", OrderSide::SELL, OrderType::LIMIT, 0.00009712, quantity)
+            ];
+            assert!(matches!(client.batch_orders(orders, None).await, Err(MexcError::InvalidRequest(_))));
+        }
+
+        // as do an oversized batch and a batch over two symbols
+        let orders = vec![OrderRequest::limit("PLSUSDT", OrderSide::SELL, 0.00009512, 100.0).unwrap(); MAX_BATCH_ORDERS + 1];
+        assert!(matches!(client.batch_orders(orders, None).await, Err(MexcError::InvalidRequest(_))));
+        let orders = vec![
+            OrderRequest::limit("PLSUSDT", OrderSide::SELL, 0.00009512, 100.0).unwrap(),
+            OrderRequest::limit("BTCUSDT", OrderSide::SELL, 70000.0, 0.001).unwrap()
+        ];
+        assert!(matches!(client.batch_orders(orders, None).await, Err(MexcError::InvalidRequest(_))));
+    }
+
+    #[tokio::test]
+    pub async fn test_market_order_by_quote_amount() {
+        let (base_url, handle) = serve(vec![
+            (200, r#"{"symbol":"PLSUSDT","orderId":"1","orderListId":-1,"price":"0","origQty":"0","type":"MARKET","side":"BUY","transactTime":1}"#),
+            (200, "[]")
+        ]).await;
+        let client = Mexc::builder().base_url(base_url).api_key("key").api_secret("secret").build().unwrap();
+
+        let order = OrderRequest::market_quote("PLSUSDT", OrderSide::BUY, 25.5).unwrap().with_client_order_id("quote&x=1");
+        client.place_order(&order, None).await.unwrap();
+        client.batch_orders(vec![OrderRequest::market("PLSUSDT", OrderSide::SELL, 100.0).unwrap()], None).await.unwrap();
+
+        let requests = handle.await.unwrap();
+        assert!(requests[0].contains("symbol=PLSUSDT&side=BUY&type=MARKET&quoteOrderQty=25.5&newClientOrderId=quote%26x%3D1&"));
+        assert!(!requests[0].contains("price="));
+        assert!(!requests[0].contains("quantity="));
+        assert!(requests[1].contains("%22quantity%22%3A%22100%22"));
+        assert!(!requests[1].contains("price"));
+    }
+
+    #[tokio::test]
+    pub async fn test_test_order() {
+        let (base_url, handle) = serve(vec![(200, "{}")]).await;
+        let client = Mexc::builder().base_url(base_url).api_key("key").api_secret("secret").build().unwrap();
+
+        let order = OrderRequest::limit("PLSUSDT", OrderSide::BUY, 0.00009512, 100.0).unwrap();
+        client.test_order(&order, None).await.unwrap();
+
+        let requests = handle.await.unwrap();
+        assert!(requests[0].starts_with("POST /api/v3/order/test?symbol=PLSUSDT&side=BUY&type=LIMIT&quantity=100&price=0.00009512&recvWindow="));
+    }
+
+    #[tokio::test]
+    pub async fn test_dry_run() {
+        // only reads reach the server
+        let (base_url, handle) = serve(vec![
+            (200, order_json("1", 10)),
+            (200, format!("[{}]", order_json("2", 20))),
+            (200, order_json("3", 30))
+        ]).await;
+        let client = Mexc::builder().base_url(base_url.clone()).api_key("key").api_secret("secret").dry_run(true).build().unwrap();
+
+        let receipt = client.submit_order("PLSUSDT", OrderSide::SELL, OrderType::LIMIT, 0.00009512, 100.0, None).await.unwrap();
+        assert_eq!(receipt.symbol, "PLSUSDT");
+        assert_eq!(receipt.orig_qty, 100.0);
+        assert_eq!(receipt.order_id, format!("dry-run-{}", receipt.client_order_id.unwrap()));
+
+        let orders = vec![
+            OrderRequest::market_quote("PLSUSDT", OrderSide::BUY, 10.0).unwrap().with_client_order_id("a"),
+            OrderRequest::limit("PLSUSDT", OrderSide::BUY, 0.00009, 100.0).unwrap()
+        ];
+        let receipts = client.batch_orders(orders, None).await.unwrap();
+        assert_eq!(receipts.len(), 2);
+        assert_eq!(receipts[0].client_order_id.as_deref(), Some("a"));
+        assert_eq!(receipts[0].order_type, OrderType::MARKET);
+        let orders = vec![OrderRequest::market("PLSUSDT", OrderSide::SELL, 100.0).unwrap(); MAX_BATCH_ORDERS + 1];
+        assert!(matches!(client.batch_orders(orders, None).await, Err(MexcError::InvalidRequest(_))));
+
+        let cancelled = client.cancel_order("PLSUSDT", OrderRef::client_id("c-1"), None).await.unwrap();
+        assert_eq!(cancelled.orig_client_order_id.as_deref(), Some("c-1"));
+        assert_eq!(cancelled.exec_qty, 40.0);
+        let cancelled = client.cancel_all_orders("PLSUSDT", None).await.unwrap();
+        assert_eq!(cancelled[0].order_id, "2");
+
+        // the placement leg is previewed with what is still open
+        let replaced = client.replace_order("PLSUSDT", "3", 0.0002, None).await.unwrap();
+        assert_eq!(replaced.remaining_qty, 60.0);
+        assert!(matches!(replaced.replacement, Replacement::Placed(ref receipt) if receipt.orig_qty == 60.0 && receipt.order_id.starts_with("dry-run-")));
+
+        // still validated and signed
+        assert!(client.submit_order("PLSUSDT", OrderSide::SELL, OrderType::LIMIT, -1.0, 100.0, None).await.is_err());
+        let unsigned = Mexc::builder().base_url(base_url).dry_run(true).build().unwrap();
+        assert!(matches!(unsigned.cancel_all_orders("PLSUSDT", None).await, Err(MexcError::MissingCredentials(_))));
+
+        let requests = handle.await.unwrap();
+        assert!(requests[0].starts_with("GET /api/v3/order?symbol=PLSUSDT&origClientOrderId=c-1&"));
+        assert!(requests[1].starts_with("GET /api/v3/openOrders?symbol=PLSUSDT&"));
+        assert!(requests[2].starts_with("GET /api/v3/order?symbol=PLSUSDT&orderId=3&"));
+    }
+
+    #[cfg(feature = "decimal")]
+    #[tokio::test]
+    pub async fn test_decimal_orders() {
+        use std::str::FromStr;
+        use crate::num::Decimal;
+
+        let (base_url, handle) = serve(vec![
+            (200, r#"{"symbol":"PLSUSDT","orderId":"1","orderListId":-1,"price":"0.30","origQty":"100.10","type":"LIMIT","side":"BUY","transactTime":1}"#)
+        ]).await;
+        let client = Mexc::builder().base_url(base_url).api_key("key").api_secret("secret").build().unwrap();
+
+        let price = Decimal::from_str("0.1").unwrap() + Decimal::from_str("0.20").unwrap();
+        let order = OrderRequest::limit("PLSUSDT", OrderSide::BUY, price, Decimal::from_str("100.10").unwrap()).unwrap();
+        let receipt: OrderReceipt<Decimal> = client.place_order_as(&order, None).await.unwrap();
+        assert_eq!(receipt.price.to_string(), "0.30");
+        assert_eq!(receipt.orig_qty, Decimal::from_str("100.1").unwrap());
+
+        let requests = handle.await.unwrap();
+        assert!(requests[0].contains("&quantity=100.10&price=0.30&"));
+    }
+
+    fn cancelled_json(symbol: &str, order_id: &str, orig_qty: &str, exec_qty: &str) -> String {
+        format!(r#"{{"symbol":"{symbol}","origClientOrderId":null,"orderId":"{order_id}","clientOrderId":null,"price":"0.0001","origQty":"{orig_qty}","executedQty":"{exec_qty}","cummulativeQuoteQty":"0","status":"CANCELED","timeInForce":null,"type":"LIMIT","side":"SELL"}}"#)
+    }
+
+    #[tokio::test]
+    pub async fn test_replace_order() {
+        let (base_url, handle) = serve(vec![
+            (200, cancelled_json("PLSUSDT", "1", "100.3", "40.1")),
+            (200, r#"{"symbol":"PLSUSDT","orderId":"2","orderListId":-1,"price":"0.0002","origQty":"60.2","type":"LIMIT","side":"SELL","transactTime":1}"#.to_string()),
+            (200, cancelled_json("PLSUSDT", "2", "60.2", "60.2"))
+        ]).await;
+        let client = Mexc::builder().base_url(base_url).api_key("key").api_secret("secret").build().unwrap();
+
+        let replaced = client.replace_order("PLSUSDT", "1", 0.0002, None).await.unwrap();
+        assert_eq!(replaced.remaining_qty, 60.2);
+        assert!(matches!(replaced.replacement, Replacement::Placed(ref receipt) if receipt.order_id == "2"));
+
+        let replaced = client.replace_order("PLSUSDT", "2", 0.0003, None).await.unwrap();
+        assert_eq!(replaced.remaining_qty, 0.0);
+        assert!(matches!(replaced.replacement, Replacement::Filled));
+
+        let requests = handle.await.unwrap();
+        assert!(requests[0].starts_with("DELETE /api/v3/order?symbol=PLSUSDT&orderId=1&"));
+        assert!(requests[1].starts_with("POST /api/v3/order?symbol=PLSUSDT&side=SELL&type=LIMIT&quantity=60.2&price=0.0002&"));
+        assert_eq!(requests.len(), 3);
+    }
+
+    #[cfg(feature = "decimal")]
+    #[tokio::test]
+    pub async fn test_decimal_replace_order() {
+        use crate::num::Decimal;
+
+        let (base_url, handle) = serve(vec![
+            (200, cancelled_json("PLSUSDT", "1", "100.30", "40.1")),
+            (200, r#"{"symbol":"PLSUSDT","orderId":"2","orderListId":-1,"price":"0.0002","origQty":"60.20","type":"LIMIT","side":"SELL","transactTime":1}"#.to_string())
+        ]).await;
+        let client = Mexc::builder().base_url(base_url).api_key("key").api_secret("secret").build().unwrap();
+
+        let replaced = client.replace_order_as::<Decimal>("PLSUSDT", "1", 0.0002, None).await.unwrap();
+        assert_eq!(replaced.remaining_qty.to_string(), "60.20");
+
+        let requests = handle.await.unwrap();
+        assert!(requests[1].contains("&quantity=60.20&price=0.0002&"));
+    }
+
+    #[tokio::test]
+    pub async fn test_replace_orders() {
+        let (base_url, handle) = serve_with(6, |request| {
+            let order_id = |id: &str| request.contains(&format!("orderId={id}&"));
+            match request {
+                r if r.starts_with("DELETE") && order_id("1") => (200, cancelled_json("PLSUSDT", "1", "100", "0")),
+                r if r.starts_with("DELETE") && order_id("2") => (200, cancelled_json("PLSUSDT", "2", "100", "25")),
+                r if r.starts_with("DELETE") && order_id("3") => (200, cancelled_json("MXUSDT", "3", "5", "0")),
+                r if r.starts_with("DELETE") => (400, r#"{"code":-2011,"msg":"Unknown order"}"#.to_string()),
+                r if r.contains("PLSUSDT") => {
+                    // one receipt per client order id in the batch
+                    let receipts: Vec<String> = r.split("newClientOrderId%22%3A%22").skip(1)
+                        .map(|rest| format!(r#"{{"symbol":"PLSUSDT","orderId":"new","orderListId":-1,"clientOrderId":"{}","price":"0.0002","origQty":"0","type":"LIMIT","side":"SELL","transactTime":1}}"#, &rest[..32]))
+                        .collect();
+                    (200, format!("[{}]", receipts.join(",")))
+                }
+                _ => (400, r#"{"code":30004,"msg":"Insufficient position"}"#.to_string())
+            }
+        }).await;
+        let client = Mexc::builder().base_url(base_url).api_key("key").api_secret("secret").build().unwrap();
+
+        let results = client.replace_orders(vec![
+            OrderReplace::new("PLSUSDT", "1", 0.0002),
+            OrderReplace::new("PLSUSDT", "2", 0.0002),
+            OrderReplace::new("MXUSDT", "3", 2.5),
+            OrderReplace::new("MXUSDT", "4", 2.5)
+        ], None).await;
+
+        let results: Vec<_> = results.into_iter().map(|result| result.map(|replaced| (replaced.remaining_qty, replaced.replacement))).collect();
+        assert!(matches!(results[0], Ok((100.0, Replacement::Placed(_)))));
+        assert!(matches!(results[1], Ok((75.0, Replacement::Placed(_)))));
+        assert!(matches!(results[2], Ok((5.0, Replacement::Failed(ref err))) if matches!(**err, MexcError::Api { code: 30004, .. })));
+        assert!(matches!(results[3], Err(MexcError::Api { code: -2011, .. })));
+
+        let requests = handle.await.unwrap();
+        assert_eq!(requests.iter().filter(|request| request.starts_with("POST /api/v3/batchOrders")).count(), 2);
+    }
+
+    #[test]
+    pub fn test_invalid_order_requests() {
+        assert!(OrderRequest::limit("PLSUSDT", OrderSide::BUY, 0.0, 100.0).is_err());
+        assert!(OrderRequest::market("PLSUSDT", OrderSide::BUY, -1.0).is_err());
+        assert!(OrderRequest::market_quote("PLSUSDT", OrderSide::BUY, f64::NAN).is_err());
+        assert!(OrderRequest::from_parts("PLSUSDT", OrderSide::BUY, OrderType::FILL_OR_KILL, f64::INFINITY, 1.0).is_err());
+
+        let order = OrderRequest::from_parts("PLSUSDT", OrderSide::SELL, OrderType::MARKET, 0.0, 100.0).unwrap();
+        assert_eq!(order.to_query(), "symbol=PLSUSDT&side=SELL&type=MARKET&quantity=100");
+        let order = OrderRequest::limit_maker("PLSUSDT", OrderSide::SELL, 0.5, 100.0).unwrap();
+        assert_eq!(order.to_query(), "symbol=PLSUSDT&side=SELL&type=LIMIT_MAKER&quantity=100&price=0.5");
+    }
 
     #[tokio::test]
     pub async fn test_get_server_time() {
@@ -25,6 +542,21 @@ mod tests {
         dbg!(time);
     }
 
+    #[tokio::test]
+    pub async fn test_ping_checks_the_response() {
+        let (base_url, handle) = serve(vec![
+            (200, "{}"),
+            (503, "<html>Service Unavailable</html>"),
+            (200, "<html>Sign in to the network</html>")
+        ]).await;
+        let client = Mexc::builder().base_url(base_url).build().unwrap();
+
+        assert!(client.ping().await.is_ok());
+        assert!(matches!(client.ping().await, Err(MexcError::Http { .. })));
+        assert!(client.ping().await.is_err());
+        assert!(handle.await.unwrap()[0].starts_with("GET /api/v3/ping"));
+    }
+
     #[tokio::test]
     pub async fn test_ping() {
 
@@ -56,6 +588,36 @@ mod tests {
         dbg!(info);
     }
 
+    #[tokio::test]
+    pub async fn test_get_klines() {
+        let client = Mexc::new(None,None,None).unwrap();
+        let klines = client.get_klines("PLSUSDT", KlineInterval::Min60, None, None, Some(5)).await.unwrap();
+        dbg!(klines);
+    }
+
+    #[tokio::test]
+    pub async fn test_get_trades() {
+        let client = Mexc::new(None,None,None).unwrap();
+        let trades = client.get_trades("PLSUSDT", Some(5)).await.unwrap();
+        dbg!(trades);
+    }
+
+    #[tokio::test]
+    pub async fn test_get_price_tickers() {
+        let client = Mexc::new(None,None,None).unwrap();
+        let tickers = client.get_price_tickers().await.unwrap();
+        dbg!(tickers.len());
+    }
+
+    #[tokio::test]
+    pub async fn test_get_my_trades() {
+        let (key, secret) = unlock_keys().unwrap();
+        let client = Mexc::new(Some(key),Some(secret),None).unwrap();
+
+        let trades = client.get_my_trades("PLSUSDT", None, None, None, Some(10), None).await.unwrap();
+        dbg!(trades);
+    }
+
     #[tokio::test]
     pub async fn test_send_order() {
         let (key, secret) = unlock_keys().unwrap();
@@ -131,7 +693,8 @@ mod tests {
             price: 0.00009512,
             quantity: q,
             side: OrderSide::SELL,
-            order_type: OrderType::LIMIT
+            order_type: OrderType::LIMIT,
+            client_order_id: None
         });
         
         orders.push(Order {
@@ -139,7 +702,8 @@ mod tests {
             price: 0.00009712,
             quantity: q,
             side: OrderSide::SELL,
-            order_type: OrderType::LIMIT
+            order_type: OrderType::LIMIT,
+            client_order_id: None
         });
 
         let res = client.batch_orders(orders, None).await.unwrap();
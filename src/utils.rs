@@ -9,6 +9,11 @@ pub fn get_timestamp() -> u128 {
     SystemTime::now().duration_since(UNIX_EPOCH).unwrap().as_millis()
 }
 
+/// Percent-encodes a caller supplied value for a query string
+pub(crate) fn encode(value: &str) -> String {
+    url::form_urlencoded::byte_serialize(value.as_bytes()).collect()
+}
+
 pub fn parse_string_to_f64<'de, D>(deserializer: D) -> Result<f64, D::Error>
 where
     D: Deserializer<'de>,
@@ -22,6 +27,48 @@ where
     }
 }
 
+pub fn parse_string_to_u64<'de, D>(deserializer: D) -> Result<u64, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value: Value = Deserialize::deserialize(deserializer)?;
+    match value {
+        Value::String(s) => s.parse::<u64>().map_err(serde::de::Error::custom),
+        Value::Number(n) => n.as_u64().ok_or_else(|| serde::de::Error::custom("Invalid number")),
+        Value::Null => Ok(0),
+        _ => Err(serde::de::Error::custom("Invalid type")),
+    }
+}
+
+/// Empty strings and nulls, which some market data fields carry, become `None`
+pub fn parse_optional_f64<'de, D>(deserializer: D) -> Result<Option<f64>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value: Value = Deserialize::deserialize(deserializer)?;
+    match value {
+        Value::String(s) if s.is_empty() => Ok(None),
+        Value::String(s) => s.parse::<f64>().map(Some).map_err(serde::de::Error::custom),
+        Value::Number(n) => Ok(n.as_f64()),
+        Value::Null => Ok(None),
+        _ => Err(serde::de::Error::custom("Invalid type")),
+    }
+}
+
+pub fn parse_bool<'de, D>(deserializer: D) -> Result<bool, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value: Value = Deserialize::deserialize(deserializer)?;
+    match value {
+        Value::Bool(b) => Ok(b),
+        Value::Number(n) => Ok(n.as_f64().is_some_and(|n| n != 0.0)),
+        Value::String(s) => Ok(s == "true" || s == "1"),
+        Value::Null => Ok(false),
+        _ => Err(serde::de::Error::custom("Invalid type")),
+    }
+}
+
 pub fn serialize_f64_as_string<S>(x: &f64, serializer: S) -> Result<S::Ok, S::Error>
 where
     S: Serializer,
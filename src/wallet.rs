@@ -0,0 +1,785 @@
+use std::fmt;
+use std::fmt::Write;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use crate::{Mexc, MexcError, MexcResult, rate_limit::weights, utils::{encode, parse_bool, parse_string_to_f64, parse_string_to_u64}};
+use crate::num::Numeric;
+use crate::orders::DEFAULT_RECV_WINDOW;
+
+/// Upper bound of `limit` on the deposit and withdraw history
+pub const MAX_HISTORY_LIMIT: u32 = 1000;
+
+/// Most assets `convert_dust` takes in one call
+pub const MAX_DUST_ASSETS: usize = 15;
+
+/// Network name as the capital endpoints use it, e.g. `TRC20` or `ERC20`; see `get_coin_config`
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
+#[serde(transparent)]
+pub struct Network(pub String);
+
+impl Network {
+    pub fn new(network: impl Into<String>) -> Self {
+        Network(network.into())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<&str> for Network {
+    fn from(network: &str) -> Self {
+        Network(network.to_string())
+    }
+}
+
+impl From<String> for Network {
+    fn from(network: String) -> Self {
+        Network(network)
+    }
+}
+
+// https://mexcdevelop.github.io/apidocs/spot_v3_en/#deposit-history-supporting-network
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(from = "i32")]
+pub enum DepositStatus {
+    Small,
+    TimeDelay,
+    LargeDelay,
+    Pending,
+    Success,
+    Auditing,
+    Rejected,
+    Refund,
+    PreSuccess,
+    Invalid,
+    Restricted,
+    Completed,
+    Unknown(i32)
+}
+
+impl From<i32> for DepositStatus {
+    fn from(status: i32) -> Self {
+        match status {
+            1 => DepositStatus::Small,
+            2 => DepositStatus::TimeDelay,
+            3 => DepositStatus::LargeDelay,
+            4 => DepositStatus::Pending,
+            5 => DepositStatus::Success,
+            6 => DepositStatus::Auditing,
+            7 => DepositStatus::Rejected,
+            8 => DepositStatus::Refund,
+            9 => DepositStatus::PreSuccess,
+            10 => DepositStatus::Invalid,
+            11 => DepositStatus::Restricted,
+            12 => DepositStatus::Completed,
+            other => DepositStatus::Unknown(other)
+        }
+    }
+}
+
+impl DepositStatus {
+    pub fn code(&self) -> i32 {
+        match self {
+            DepositStatus::Small => 1,
+            DepositStatus::TimeDelay => 2,
+            DepositStatus::LargeDelay => 3,
+            DepositStatus::Pending => 4,
+            DepositStatus::Success => 5,
+            DepositStatus::Auditing => 6,
+            DepositStatus::Rejected => 7,
+            DepositStatus::Refund => 8,
+            DepositStatus::PreSuccess => 9,
+            DepositStatus::Invalid => 10,
+            DepositStatus::Restricted => 11,
+            DepositStatus::Completed => 12,
+            DepositStatus::Unknown(code) => *code
+        }
+    }
+}
+
+// https://mexcdevelop.github.io/apidocs/spot_v3_en/#withdraw-history-supporting-network
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(from = "i32")]
+pub enum WithdrawStatus {
+    Apply,
+    Auditing,
+    Wait,
+    Processing,
+    WaitPackaging,
+    WaitConfirm,
+    Success,
+    Failed,
+    Cancel,
+    Manual,
+    Unknown(i32)
+}
+
+impl From<i32> for WithdrawStatus {
+    fn from(status: i32) -> Self {
+        match status {
+            1 => WithdrawStatus::Apply,
+            2 => WithdrawStatus::Auditing,
+            3 => WithdrawStatus::Wait,
+            4 => WithdrawStatus::Processing,
+            5 => WithdrawStatus::WaitPackaging,
+            6 => WithdrawStatus::WaitConfirm,
+            7 => WithdrawStatus::Success,
+            8 => WithdrawStatus::Failed,
+            9 => WithdrawStatus::Cancel,
+            10 => WithdrawStatus::Manual,
+            other => WithdrawStatus::Unknown(other)
+        }
+    }
+}
+
+impl WithdrawStatus {
+    pub fn code(&self) -> i32 {
+        match self {
+            WithdrawStatus::Apply => 1,
+            WithdrawStatus::Auditing => 2,
+            WithdrawStatus::Wait => 3,
+            WithdrawStatus::Processing => 4,
+            WithdrawStatus::WaitPackaging => 5,
+            WithdrawStatus::WaitConfirm => 6,
+            WithdrawStatus::Success => 7,
+            WithdrawStatus::Failed => 8,
+            WithdrawStatus::Cancel => 9,
+            WithdrawStatus::Manual => 10,
+            WithdrawStatus::Unknown(code) => *code
+        }
+    }
+
+    /// No further change is expected
+    pub fn is_final(&self) -> bool {
+        matches!(self, WithdrawStatus::Success | WithdrawStatus::Failed | WithdrawStatus::Cancel)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct CoinConfig {
+    pub coin: String,
+    pub name: String,
+    #[serde(rename = "networkList")]
+    pub networks: Vec<NetworkConfig>
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct NetworkConfig {
+    pub coin: String,
+    /// Name to pass as the network of deposits and withdrawals
+    #[serde(rename = "netWork")]
+    pub network: Network,
+    /// Display name, e.g. `Tron(TRC20)`
+    #[serde(rename = "network", default)]
+    pub network_name: String,
+    pub name: Option<String>,
+    #[serde(rename = "depositEnable", default, deserialize_with = "parse_bool")]
+    pub deposit_enabled: bool,
+    #[serde(rename = "withdrawEnable", default, deserialize_with = "parse_bool")]
+    pub withdraw_enabled: bool,
+    #[serde(rename = "minConfirm", default)]
+    pub min_confirm: u32,
+    #[serde(rename = "withdrawFee", default, deserialize_with = "parse_string_to_f64")]
+    pub withdraw_fee: f64,
+    #[serde(rename = "withdrawMin", default, deserialize_with = "parse_string_to_f64")]
+    pub withdraw_min: f64,
+    #[serde(rename = "withdrawMax", default, deserialize_with = "parse_string_to_f64")]
+    pub withdraw_max: f64,
+    #[serde(rename = "withdrawIntegerMultiple", default)]
+    pub withdraw_integer_multiple: Option<String>,
+    #[serde(rename = "sameAddress", default, deserialize_with = "parse_bool")]
+    pub same_address: bool,
+    #[serde(default)]
+    pub contract: Option<String>,
+    #[serde(rename = "depositDesc", default)]
+    pub deposit_desc: Option<String>,
+    #[serde(rename = "depositTips", default)]
+    pub deposit_tips: Option<String>,
+    #[serde(rename = "withdrawTips", default)]
+    pub withdraw_tips: Option<String>
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct DepositAddress {
+    pub coin: String,
+    pub network: Network,
+    pub address: String,
+    /// Tag or memo some networks need next to the address
+    #[serde(default)]
+    pub memo: Option<String>
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Deposit {
+    pub coin: String,
+    pub network: Network,
+    #[serde(deserialize_with = "parse_string_to_f64")]
+    pub amount: f64,
+    pub status: DepositStatus,
+    pub address: String,
+    #[serde(default)]
+    pub memo: Option<String>,
+    #[serde(rename = "txId", default)]
+    pub tx_id: Option<String>,
+    #[serde(rename = "insertTime")]
+    pub insert_time: u128,
+    /// Confirmations needed before the deposit can be withdrawn
+    #[serde(rename = "unlockConfirm", default, deserialize_with = "parse_string_to_u64")]
+    pub unlock_confirm: u64,
+    /// Progress as reported, e.g. `12/12`
+    #[serde(rename = "confirmTimes", default)]
+    pub confirm_times: Option<String>
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Withdrawal {
+    pub id: String,
+    pub coin: String,
+    pub network: Network,
+    pub address: String,
+    #[serde(deserialize_with = "parse_string_to_f64")]
+    pub amount: f64,
+    pub status: WithdrawStatus,
+    #[serde(rename = "transactionFee", default, deserialize_with = "parse_string_to_f64")]
+    pub transaction_fee: f64,
+    #[serde(rename = "txId", default)]
+    pub tx_id: Option<String>,
+    #[serde(rename = "transHash", default)]
+    pub trans_hash: Option<String>,
+    #[serde(default)]
+    pub memo: Option<String>,
+    #[serde(default)]
+    pub remark: Option<String>,
+    #[serde(rename = "transferType", default)]
+    pub transfer_type: Option<i32>,
+    #[serde(rename = "confirmNo", default)]
+    pub confirm_no: Option<u32>,
+    #[serde(rename = "applyTime")]
+    pub apply_time: u128,
+    #[serde(rename = "updateTime", default)]
+    pub update_time: Option<u128>
+}
+
+/// Wallets funds move between with `transfer`
+#[allow(non_camel_case_types)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountType {
+    SPOT,
+    #[serde(alias = "FUTURE")]
+    FUTURES
+}
+
+impl fmt::Display for AccountType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccountType::SPOT => write!(f, "SPOT"),
+            AccountType::FUTURES => write!(f, "FUTURES"),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum TransferStatus {
+    Success,
+    Failed,
+    Pending,
+    #[serde(other)]
+    Unknown
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TransferReceipt {
+    #[serde(rename = "tranId")]
+    pub tran_id: String,
+    /// Looked up right after the transfer, `None` when that lookup failed
+    #[serde(default)]
+    pub status: Option<TransferStatus>
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct Transfer {
+    #[serde(rename = "tranId")]
+    pub tran_id: String,
+    #[serde(rename = "clientTranId", default)]
+    pub client_tran_id: Option<String>,
+    pub asset: String,
+    #[serde(deserialize_with = "parse_string_to_f64")]
+    pub amount: f64,
+    #[serde(rename = "fromAccountType")]
+    pub from: AccountType,
+    #[serde(rename = "toAccountType")]
+    pub to: AccountType,
+    pub status: TransferStatus,
+    pub timestamp: u128
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct TransferPage {
+    pub rows: Vec<Transfer>,
+    /// Transfers matching the query over all pages
+    pub total: u64
+}
+
+/// A small balance that can be converted to MX
+#[derive(Deserialize, Debug, Clone)]
+pub struct DustAsset {
+    pub asset: String,
+    #[serde(deserialize_with = "parse_string_to_f64")]
+    pub balance: f64,
+    #[serde(rename = "convertMx", deserialize_with = "parse_string_to_f64")]
+    pub convert_mx: f64,
+    #[serde(rename = "convertUsdt", deserialize_with = "parse_string_to_f64")]
+    pub convert_usdt: f64,
+    /// Set with `message` when the asset cannot be converted right now
+    #[serde(default)]
+    pub code: Option<String>,
+    #[serde(default)]
+    pub message: Option<String>
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+pub struct DustConversion {
+    #[serde(rename = "successList")]
+    pub converted: Vec<String>,
+    #[serde(rename = "failedList")]
+    pub failed: Vec<String>,
+    /// MX received
+    #[serde(rename = "totalConvert", deserialize_with = "parse_string_to_f64")]
+    pub total_convert: f64,
+    #[serde(rename = "convertFee", deserialize_with = "parse_string_to_f64")]
+    pub convert_fee: f64
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct DustLogDetail {
+    pub id: String,
+    pub asset: String,
+    #[serde(deserialize_with = "parse_string_to_f64")]
+    pub amount: f64,
+    /// MX received
+    #[serde(deserialize_with = "parse_string_to_f64")]
+    pub convert: f64,
+    #[serde(deserialize_with = "parse_string_to_f64")]
+    pub fee: f64,
+    pub time: u128
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct DustLog {
+    #[serde(rename = "totalConvert", deserialize_with = "parse_string_to_f64")]
+    pub total_convert: f64,
+    #[serde(rename = "totalFee", deserialize_with = "parse_string_to_f64")]
+    pub total_fee: f64,
+    #[serde(rename = "convertTime")]
+    pub convert_time: u128,
+    #[serde(rename = "convertDetails")]
+    pub details: Vec<DustLogDetail>
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct DustLogPage {
+    #[serde(rename = "data")]
+    pub conversions: Vec<DustLog>,
+    #[serde(rename = "totalRecords")]
+    pub total: u64,
+    pub page: u32,
+    #[serde(rename = "totalPageNum")]
+    pub pages: u32
+}
+
+#[derive(Deserialize, Debug, Clone)]
+struct WithdrawId {
+    id: String
+}
+
+/// Filters shared by the deposit and withdraw history, the last 7 days by default
+#[derive(Debug, Clone, Default)]
+pub struct HistoryQuery {
+    pub coin: Option<String>,
+    pub start: Option<u128>,
+    pub end: Option<u128>,
+    /// default 1000; max 1000
+    pub limit: Option<u32>
+}
+
+impl HistoryQuery {
+    fn to_query(&self, status: Option<i32>) -> String {
+        let mut query = String::new();
+        if let Some(coin) = &self.coin {
+            let _ = write!(query, "coin={}&", encode(coin));
+        }
+        if let Some(status) = status {
+            let _ = write!(query, "status={status}&");
+        }
+        if let Some(start) = self.start {
+            let _ = write!(query, "startTime={start}&");
+        }
+        if let Some(end) = self.end {
+            let _ = write!(query, "endTime={end}&");
+        }
+        if let Some(limit) = self.limit {
+            let _ = write!(query, "limit={limit}&");
+        }
+        query
+    }
+}
+
+/// A withdrawal to `address` on `network`, the optional fields are left out unless set
+#[derive(Debug, Clone, PartialEq)]
+pub struct WithdrawRequest {
+    coin: String,
+    network: Network,
+    address: String,
+    amount: String,
+    memo: Option<String>,
+    withdraw_order_id: Option<String>,
+    contract_address: Option<String>,
+    remark: Option<String>
+}
+
+impl WithdrawRequest {
+
+    pub fn new<N: Numeric>(coin: impl Into<String>, network: impl Into<Network>, address: impl Into<String>, amount: N) -> Self {
+        Self {
+            coin: coin.into(),
+            network: network.into(),
+            address: address.into(),
+            amount: amount.to_string(),
+            memo: None,
+            withdraw_order_id: None,
+            contract_address: None,
+            remark: None
+        }
+    }
+
+    pub fn memo(mut self, memo: impl Into<String>) -> Self {
+        self.memo = Some(memo.into());
+        self
+    }
+
+    /// Own id for the withdrawal
+    pub fn withdraw_order_id(mut self, withdraw_order_id: impl Into<String>) -> Self {
+        self.withdraw_order_id = Some(withdraw_order_id.into());
+        self
+    }
+
+    pub fn contract_address(mut self, contract_address: impl Into<String>) -> Self {
+        self.contract_address = Some(contract_address.into());
+        self
+    }
+
+    pub fn remark(mut self, remark: impl Into<String>) -> Self {
+        self.remark = Some(remark.into());
+        self
+    }
+
+    fn to_query(&self) -> String {
+        let mut query = format!("coin={}&netWork={}&address={}&amount={}", encode(&self.coin), encode(self.network.as_str()), encode(&self.address), self.amount);
+        if let Some(memo) = &self.memo {
+            let _ = write!(query, "&memo={}", encode(memo));
+        }
+        if let Some(withdraw_order_id) = &self.withdraw_order_id {
+            let _ = write!(query, "&withdrawOrderId={}", encode(withdraw_order_id));
+        }
+        if let Some(contract_address) = &self.contract_address {
+            let _ = write!(query, "&contractAddress={}", encode(contract_address));
+        }
+        if let Some(remark) = &self.remark {
+            let _ = write!(query, "&remark={}", encode(remark));
+        }
+        query
+    }
+}
+
+impl Mexc {
+
+    /// Every coin with its networks, fees and deposit or withdraw availability
+    pub async fn get_coin_config(&self, recv_window: Option<u64>) -> MexcResult<Vec<CoinConfig>> {
+        let recv_window = recv_window.unwrap_or(DEFAULT_RECV_WINDOW);
+
+        let request = format!("recvWindow={recv_window}");
+        self.send_signed(Method::GET, "/api/v3/capital/config/getall", &request, weights::CAPITAL_CONFIG).await
+    }
+
+    /// Creates a deposit address for `coin` on `network`
+    pub async fn generate_deposit_address(&self, coin: &str, network: &Network, recv_window: Option<u64>) -> MexcResult<DepositAddress> {
+        let recv_window = recv_window.unwrap_or(DEFAULT_RECV_WINDOW);
+
+        let request = format!("coin={}&network={}&recvWindow={recv_window}", encode(coin), encode(network.as_str()));
+        self.send_signed(Method::POST, "/api/v3/capital/deposit/address", &request, weights::DEFAULT).await
+    }
+
+    /// Existing deposit addresses of `coin`, on every network unless one is given
+    pub async fn get_deposit_addresses(&self, coin: &str, network: Option<&Network>, recv_window: Option<u64>) -> MexcResult<Vec<DepositAddress>> {
+        let recv_window = recv_window.unwrap_or(DEFAULT_RECV_WINDOW);
+
+        let mut request = format!("coin={}", encode(coin));
+        if let Some(network) = network {
+            let _ = write!(request, "&network={}", encode(network.as_str()));
+        }
+        let _ = write!(request, "&recvWindow={recv_window}");
+        self.send_signed(Method::GET, "/api/v3/capital/deposit/address", &request, weights::DEPOSIT_ADDRESS).await
+    }
+
+    pub async fn get_deposit_history(&self, query: &HistoryQuery, status: Option<DepositStatus>, recv_window: Option<u64>) -> MexcResult<Vec<Deposit>> {
+        let recv_window = recv_window.unwrap_or(DEFAULT_RECV_WINDOW);
+
+        let request = format!("{}recvWindow={recv_window}", query.to_query(status.map(|status| status.code())));
+        self.send_signed(Method::GET, "/api/v3/capital/deposit/hisrec", &request, weights::DEFAULT).await
+    }
+
+    /// Submits a withdrawal, returns its id. Never retried.
+    pub async fn withdraw(&self, withdrawal: &WithdrawRequest, recv_window: Option<u64>) -> MexcResult<String> {
+        let recv_window = recv_window.unwrap_or(DEFAULT_RECV_WINDOW);
+
+        let request = format!("{}&recvWindow={recv_window}", withdrawal.to_query());
+        let resp: WithdrawId = self.send_signed(Method::POST, "/api/v3/capital/withdraw", &request, weights::DEFAULT).await?;
+        Ok(resp.id)
+    }
+
+    /// Cancels a withdrawal that is still waiting, returns its id
+    pub async fn cancel_withdraw(&self, id: &str, recv_window: Option<u64>) -> MexcResult<String> {
+        let recv_window = recv_window.unwrap_or(DEFAULT_RECV_WINDOW);
+
+        let request = format!("id={}&recvWindow={recv_window}", encode(id));
+        let resp: WithdrawId = self.send_signed(Method::DELETE, "/api/v3/capital/withdraw", &request, weights::DEFAULT).await?;
+        Ok(resp.id)
+    }
+
+    /// Moves `amount` of `asset` between the spot and futures wallets. The exchange only answers with
+    /// the transfer id, the status in the receipt comes from a `get_transfer` right after; a pending
+    /// transfer can be polled with `get_transfer`.
+    pub async fn transfer<N: Numeric>(&self, from: AccountType, to: AccountType, asset: &str, amount: N, recv_window: Option<u64>) -> MexcResult<TransferReceipt> {
+        let recv_window = recv_window.unwrap_or(DEFAULT_RECV_WINDOW);
+
+        let request = format!("fromAccountType={from}&toAccountType={to}&asset={}&amount={amount}&recvWindow={recv_window}", encode(asset));
+        let mut receipt: TransferReceipt = self.send_signed(Method::POST, "/api/v3/capital/transfer", &request, weights::DEFAULT).await?;
+
+        // the transfer is done either way, a failed lookup only leaves the status unknown
+        receipt.status = self.get_transfer(&receipt.tran_id, Some(recv_window)).await.ok().map(|transfer| transfer.status);
+        Ok(receipt)
+    }
+
+    pub async fn get_transfer(&self, tran_id: &str, recv_window: Option<u64>) -> MexcResult<Transfer> {
+        let recv_window = recv_window.unwrap_or(DEFAULT_RECV_WINDOW);
+
+        let request = format!("tranId={}&recvWindow={recv_window}", encode(tran_id));
+        self.send_signed(Method::GET, "/api/v3/capital/transfer/tranId", &request, weights::DEFAULT).await
+    }
+
+    /// Transfers from `from` to `to`, the last 7 days without a range. page: default 1; size: default 10, max 100
+    #[allow(clippy::too_many_arguments)]
+    pub async fn get_transfer_history(&self, from: AccountType, to: AccountType, start: Option<u128>, end: Option<u128>, page: Option<u32>, size: Option<u32>, recv_window: Option<u64>) -> MexcResult<TransferPage> {
+        let recv_window = recv_window.unwrap_or(DEFAULT_RECV_WINDOW);
+
+        let mut request = format!("fromAccountType={from}&toAccountType={to}");
+        if let Some(start) = start {
+            let _ = write!(request, "&startTime={start}");
+        }
+        if let Some(end) = end {
+            let _ = write!(request, "&endTime={end}");
+        }
+        if let Some(page) = page {
+            let _ = write!(request, "&page={page}");
+        }
+        if let Some(size) = size {
+            let _ = write!(request, "&size={size}");
+        }
+        let _ = write!(request, "&recvWindow={recv_window}");
+        self.send_signed(Method::GET, "/api/v3/capital/transfer", &request, weights::DEFAULT).await
+    }
+
+    pub async fn get_withdraw_history(&self, query: &HistoryQuery, status: Option<WithdrawStatus>, recv_window: Option<u64>) -> MexcResult<Vec<Withdrawal>> {
+        let recv_window = recv_window.unwrap_or(DEFAULT_RECV_WINDOW);
+
+        let request = format!("{}recvWindow={recv_window}", query.to_query(status.map(|status| status.code())));
+        self.send_signed(Method::GET, "/api/v3/capital/withdraw/history", &request, weights::DEFAULT).await
+    }
+
+    pub async fn get_dust_assets(&self, recv_window: Option<u64>) -> MexcResult<Vec<DustAsset>> {
+        let recv_window = recv_window.unwrap_or(DEFAULT_RECV_WINDOW);
+
+        let request = format!("recvWindow={recv_window}");
+        self.send_signed(Method::GET, "/api/v3/capital/convert/list", &request, weights::DEFAULT).await
+    }
+
+    /// Converts the whole free balance of each of `assets` to MX, at most `MAX_DUST_ASSETS` at a time
+    pub async fn convert_dust(&self, assets: &[&str], recv_window: Option<u64>) -> MexcResult<DustConversion> {
+        if assets.is_empty() || assets.len() > MAX_DUST_ASSETS {
+            return Err(MexcError::InvalidRequest(format!("dust conversion takes 1 to {MAX_DUST_ASSETS} assets, got {}", assets.len())));
+        }
+        let recv_window = recv_window.unwrap_or(DEFAULT_RECV_WINDOW);
+
+        let assets: Vec<String> = assets.iter().map(|asset| encode(asset)).collect();
+        let request = format!("asset={}&recvWindow={recv_window}", assets.join(","));
+        self.send_signed(Method::POST, "/api/v3/capital/convert", &request, weights::DUST_CONVERT).await
+    }
+
+    /// Converts every convertible balance worth less than `max_usdt`, as valued by the exchange in the
+    /// dust list. Batches go out one after the other and their results are added up; an empty result
+    /// when there is nothing to convert.
+    pub async fn convert_dust_below(&self, max_usdt: f64, recv_window: Option<u64>) -> MexcResult<DustConversion> {
+        let dust_assets = self.get_dust_assets(recv_window).await?;
+        let dust: Vec<&str> = dust_assets
+            .iter()
+            .filter(|dust| dust.code.is_none() && dust.balance > 0.0 && dust.convert_usdt < max_usdt)
+            .map(|dust| dust.asset.as_str())
+            .collect();
+
+        let mut total = DustConversion::default();
+        for batch in dust.chunks(MAX_DUST_ASSETS) {
+            let conversion = self.convert_dust(batch, recv_window).await?;
+            total.converted.extend(conversion.converted);
+            total.failed.extend(conversion.failed);
+            total.total_convert += conversion.total_convert;
+            total.convert_fee += conversion.convert_fee;
+        }
+        Ok(total)
+    }
+
+    /// Past dust conversions, the last 7 days without a range. page: default 1; limit: default 1, max 1000
+    pub async fn get_dust_log(&self, start: Option<u128>, end: Option<u128>, page: Option<u32>, limit: Option<u32>, recv_window: Option<u64>) -> MexcResult<DustLogPage> {
+        let recv_window = recv_window.unwrap_or(DEFAULT_RECV_WINDOW);
+
+        let mut request = String::new();
+        if let Some(start) = start {
+            let _ = write!(request, "startTime={start}&");
+        }
+        if let Some(end) = end {
+            let _ = write!(request, "endTime={end}&");
+        }
+        if let Some(page) = page {
+            let _ = write!(request, "page={page}&");
+        }
+        if let Some(limit) = limit {
+            let _ = write!(request, "limit={limit}&");
+        }
+        let _ = write!(request, "recvWindow={recv_window}");
+        self.send_signed(Method::GET, "/api/v3/capital/convert", &request, weights::DEFAULT).await
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::serve;
+
+    #[tokio::test]
+    pub async fn test_coin_config_and_addresses() {
+        let (base_url, handle) = serve(vec![
+            (200, r#"[{"coin":"USDT","name":"Tether","networkList":[{"coin":"USDT","depositDesc":null,"depositEnable":true,"minConfirm":20,"name":"Tether","network":"Tron(TRC20)","withdrawEnable":false,"withdrawFee":"1.000000000000000000","withdrawIntegerMultiple":null,"withdrawMax":"1000000.000000000000000000","withdrawMin":"10.000000000000000000","sameAddress":false,"contract":"TR7NHqjeKQxGTCi8q8ZY4pL8otSzgjLj6t","withdrawTips":null,"depositTips":null,"netWork":"TRC20"}]}]"#),
+            (200, r#"{"coin":"XRP","network":"XRP","address":"rwRmyGRoJkHKtojaC8SH2wxsnB2q3yNopB","memo":"123456"}"#),
+            (200, r#"[{"coin":"USDT","network":"TRC20","address":"TXyz","memo":null}]"#)
+        ]).await;
+        let client = Mexc::builder().base_url(base_url).api_key("key").api_secret("secret").build().unwrap();
+
+        let config = client.get_coin_config(None).await.unwrap();
+        let trc20 = &config[0].networks[0];
+        assert_eq!(trc20.network, Network::from("TRC20"));
+        assert!(trc20.deposit_enabled && !trc20.withdraw_enabled);
+        assert_eq!(trc20.withdraw_min, 10.0);
+
+        let address = client.generate_deposit_address("XRP", &"XRP".into(), None).await.unwrap();
+        assert_eq!(address.memo.as_deref(), Some("123456"));
+        let addresses = client.get_deposit_addresses("USDT", Some(&Network::new("TRC20")), None).await.unwrap();
+        assert_eq!(addresses[0].address, "TXyz");
+
+        let requests = handle.await.unwrap();
+        assert!(requests[0].starts_with("GET /api/v3/capital/config/getall?recvWindow="));
+        assert!(requests[1].starts_with("POST /api/v3/capital/deposit/address?coin=XRP&network=XRP&"));
+        assert!(requests[2].starts_with("GET /api/v3/capital/deposit/address?coin=USDT&network=TRC20&"));
+    }
+
+    #[tokio::test]
+    pub async fn test_withdraw_flow() {
+        let (base_url, handle) = serve(vec![
+            (200, r#"{"id":"7213fea8e94b4a5593d507237e5a555b"}"#),
+            (200, r#"{"id":"7213fea8e94b4a5593d507237e5a555b"}"#),
+            (200, r#"[{"id":"7213fea8e94b4a5593d507237e5a555b","txId":null,"coin":"USDT","network":"TRC20","address":"TXyz","amount":"50","transferType":0,"status":9,"transactionFee":"1","confirmNo":null,"applyTime":1665300874000,"remark":"rent","memo":null,"transHash":null,"updateTime":1665300900000,"coinId":"128f589271cb4951b03e71e6323eb7be","vcoinId":"af42c6414b9a46c8869ce30fd51660f9"}]"#),
+            (200, r#"[{"amount":"50","coin":"USDT","network":"TRC20","status":5,"address":"TXyz","txId":"0xabc","insertTime":1665300000000,"unlockConfirm":20,"confirmTimes":"20/20","memo":null}]"#)
+        ]).await;
+        let client = Mexc::builder().base_url(base_url).api_key("key").api_secret("secret").build().unwrap();
+
+        let withdrawal = WithdrawRequest::new("USDT", "TRC20", "TXyz", 50.0).remark("office rent");
+        let id = client.withdraw(&withdrawal, None).await.unwrap();
+        assert_eq!(client.cancel_withdraw(&id, None).await.unwrap(), id);
+
+        let query = HistoryQuery { coin: Some("USDT".into()), limit: Some(10), ..Default::default() };
+        let withdrawals = client.get_withdraw_history(&query, Some(WithdrawStatus::Cancel), None).await.unwrap();
+        assert_eq!(withdrawals[0].status, WithdrawStatus::Cancel);
+        assert!(withdrawals[0].status.is_final());
+        // the coin cannot add parameters of its own
+        let query = HistoryQuery { coin: Some("USDT&status=1".into()), ..Default::default() };
+        let deposits = client.get_deposit_history(&query, None, None).await.unwrap();
+        assert_eq!(deposits[0].status, DepositStatus::Success);
+        assert_eq!(deposits[0].tx_id.as_deref(), Some("0xabc"));
+
+        let requests = handle.await.unwrap();
+        assert!(requests[0].starts_with("POST /api/v3/capital/withdraw?coin=USDT&netWork=TRC20&address=TXyz&amount=50&remark=office+rent&recvWindow="));
+        assert!(requests[1].starts_with(&format!("DELETE /api/v3/capital/withdraw?id={id}&")));
+        assert!(requests[2].starts_with("GET /api/v3/capital/withdraw/history?coin=USDT&status=9&limit=10&recvWindow="));
+        assert!(requests[3].starts_with("GET /api/v3/capital/deposit/hisrec?coin=USDT%26status%3D1&recvWindow="));
+    }
+
+    #[tokio::test]
+    pub async fn test_transfer() {
+        let record = r#"{"tranId":"cb28c88cd20c42df929ebbc0a7d55a73","clientTranId":null,"asset":"USDT","amount":"25.5","fromAccountType":"SPOT","toAccountType":"FUTURES","fromSymbol":null,"toSymbol":null,"status":"SUCCESS","timestamp":1678603205000}"#;
+        let (base_url, handle) = serve(vec![
+            (200, r#"{"tranId":"cb28c88cd20c42df929ebbc0a7d55a73"}"#.to_string()),
+            (200, record.to_string()),
+            (200, record.to_string()),
+            (200, format!(r#"{{"rows":[{record}],"total":1}}"#)),
+            (200, r#"{"tranId":"cb28c88cd20c42df929ebbc0a7d55a74"}"#.to_string()),
+            (400, r#"{"code":700007,"msg":"No permission to access the endpoint."}"#.to_string())
+        ]).await;
+        let client = Mexc::builder().base_url(base_url).api_key("key").api_secret("secret").build().unwrap();
+
+        let receipt = client.transfer(AccountType::SPOT, AccountType::FUTURES, "USDT", 25.5, None).await.unwrap();
+        assert_eq!(receipt.status, Some(TransferStatus::Success));
+        let transfer = client.get_transfer(&receipt.tran_id, None).await.unwrap();
+        assert_eq!(transfer.status, TransferStatus::Success);
+        assert_eq!(transfer.to, AccountType::FUTURES);
+        assert_eq!(transfer.amount, 25.5);
+        let page = client.get_transfer_history(AccountType::SPOT, AccountType::FUTURES, Some(1678600000000), None, None, Some(100), None).await.unwrap();
+        assert_eq!(page.total, 1);
+        // sent, but the status could not be looked up
+        let receipt = client.transfer(AccountType::FUTURES, AccountType::SPOT, "USDT", 1.0, None).await.unwrap();
+        assert_eq!(receipt.status, None);
+
+        let requests = handle.await.unwrap();
+        assert!(requests[0].starts_with("POST /api/v3/capital/transfer?fromAccountType=SPOT&toAccountType=FUTURES&asset=USDT&amount=25.5&recvWindow="));
+        assert!(requests[1].starts_with("GET /api/v3/capital/transfer/tranId?tranId=cb28c88cd20c42df929ebbc0a7d55a73&"));
+        assert!(requests[3].starts_with("GET /api/v3/capital/transfer?fromAccountType=SPOT&toAccountType=FUTURES&startTime=1678600000000&size=100&"));
+    }
+
+    #[tokio::test]
+    pub async fn test_convert_dust_below() {
+        let (base_url, handle) = serve(vec![
+            (200, r#"[{"convertMx":"0.0091","convertUsdt":"0.0201","balance":"0.00001","asset":"ETH","code":null,"message":null},{"convertMx":"0.45","convertUsdt":"1.0","balance":"100","asset":"PEPE","code":null,"message":null},{"convertMx":"13.5","convertUsdt":"30","balance":"0.0005","asset":"BTC","code":null,"message":null},{"convertMx":"0.1","convertUsdt":"0.2","balance":"0.2","asset":"LOCK","code":"30","message":"you can not convert"}]"#),
+            (200, r#"{"successList":["ETH","PEPE"],"failedList":[],"totalConvert":"0.4591","convertFee":"0.0046"}"#),
+            (200, r#"{"data":[{"totalConvert":"0.4591","totalFee":"0.0046","convertTime":1665360000000,"convertDetails":[{"id":"1","convert":"0.0091","fee":"0.0001","amount":"0.00001","time":1665360000000,"asset":"ETH"}]}],"totalRecords":1,"page":1,"totalPageNum":1}"#)
+        ]).await;
+        let client = Mexc::builder().base_url(base_url).api_key("key").api_secret("secret").build().unwrap();
+
+        // BTC is above the threshold, LOCK is not convertible
+        let conversion = client.convert_dust_below(5.0, None).await.unwrap();
+        assert_eq!(conversion.converted, vec!["ETH", "PEPE"]);
+        assert_eq!(conversion.total_convert, 0.4591);
+        let log = client.get_dust_log(Some(1665300000000), None, None, Some(10), None).await.unwrap();
+        assert_eq!(log.conversions[0].details[0].asset, "ETH");
+        assert!(client.convert_dust(&[], None).await.is_err());
+
+        let requests = handle.await.unwrap();
+        assert!(requests[0].starts_with("GET /api/v3/capital/convert/list?recvWindow="));
+        assert!(requests[1].starts_with("POST /api/v3/capital/convert?asset=ETH,PEPE&recvWindow="));
+        assert!(requests[2].starts_with("GET /api/v3/capital/convert?startTime=1665300000000&limit=10&"));
+    }}
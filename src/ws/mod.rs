@@ -0,0 +1,493 @@
+pub mod structures;
+pub mod proto;
+pub mod user_data;
+
+use std::collections::BTreeSet;
+use std::time::Duration;
+use futures_util::{SinkExt, StreamExt};
+use serde_json::json;
+use tokio::net::TcpStream;
+use tokio::sync::mpsc;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use tokio_tungstenite::tungstenite::Message;
+use crate::{MexcError, MexcResult};
+
+use structures::*;
+use proto::decode_spot_binary;
+
+/// Endpoint of the legacy json streams
+pub const SPOT_WS_URL: &str = "wss://wbs.mexc.com/ws";
+/// Endpoint of the protobuf streams
+pub const SPOT_WS_PB_URL: &str = "wss://wbs-api.mexc.com/ws";
+
+/// The server drops connections without traffic for 60 seconds
+pub const DEFAULT_PING_INTERVAL: Duration = Duration::from_secs(20);
+pub const DEFAULT_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+pub const DEFAULT_EVENT_BUFFER: usize = 1024;
+
+/// Maximum number of topics per subscription request
+pub const MAX_TOPICS_PER_REQUEST: usize = 30;
+
+pub(crate) type WsStream = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+/// Endpoint matching the encoding of the typed channels
+pub fn default_url(format: WsFormat) -> &'static str {
+    match format {
+        WsFormat::Protobuf => SPOT_WS_PB_URL,
+        WsFormat::Json => SPOT_WS_URL
+    }
+}
+
+pub(crate) async fn connect(url: &str) -> MexcResult<WsStream> {
+    let (stream, _) = tokio_tungstenite::connect_async(url).await?;
+    Ok(stream)
+}
+
+enum Command {
+    Subscribe(Vec<String>),
+    Unsubscribe(Vec<String>),
+    /// Drop the current connection and reconnect, optionally to a new url
+    Reconnect(Option<String>),
+    Close
+}
+
+enum SessionEnd {
+    Closed,
+    Dropped
+}
+
+pub struct MexcSpotWsBuilder {
+    url: Option<String>,
+    format: WsFormat,
+    ping_interval: Duration,
+    reconnect_delay: Duration,
+    event_buffer: usize
+}
+
+impl Default for MexcSpotWsBuilder {
+    fn default() -> Self {
+        Self {
+            url: None,
+            format: WsFormat::default(),
+            ping_interval: DEFAULT_PING_INTERVAL,
+            reconnect_delay: DEFAULT_RECONNECT_DELAY,
+            event_buffer: DEFAULT_EVENT_BUFFER
+        }
+    }
+}
+
+impl MexcSpotWsBuilder {
+
+    /// Defaults to the endpoint matching the format
+    pub fn url(mut self, url: impl Into<String>) -> Self {
+        self.url = Some(url.into());
+        self
+    }
+
+    /// Encoding requested for the typed channels
+    pub fn format(mut self, format: WsFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn ping_interval(mut self, ping_interval: Duration) -> Self {
+        self.ping_interval = ping_interval;
+        self
+    }
+
+    pub fn reconnect_delay(mut self, reconnect_delay: Duration) -> Self {
+        self.reconnect_delay = reconnect_delay;
+        self
+    }
+
+    /// Capacity of the event channel, the connection waits for the consumer when it is full
+    pub fn event_buffer(mut self, event_buffer: usize) -> Self {
+        self.event_buffer = event_buffer;
+        self
+    }
+
+    /// Opens the connection and spawns the task driving it. Events arrive on the returned receiver,
+    /// dropping it shuts the connection down.
+    pub async fn connect(self) -> MexcResult<(MexcSpotWs, mpsc::Receiver<SpotWsEvent>)> {
+        let url = self.url.unwrap_or_else(|| default_url(self.format).to_string());
+        let stream = connect(&url).await?;
+
+        let (command_tx, command_rx) = mpsc::unbounded_channel();
+        let (event_tx, event_rx) = mpsc::channel(self.event_buffer.max(1));
+
+        let worker = Worker {
+            url,
+            ping_interval: self.ping_interval,
+            reconnect_delay: self.reconnect_delay,
+            topics: BTreeSet::new(),
+            commands: command_rx,
+            events: event_tx
+        };
+        tokio::spawn(worker.run(stream));
+
+        Ok((MexcSpotWs { commands: command_tx, format: self.format }, event_rx))
+    }
+}
+
+/// Handle to a spot market data connection, cheap to clone
+#[derive(Clone)]
+pub struct MexcSpotWs {
+    commands: mpsc::UnboundedSender<Command>,
+    format: WsFormat
+}
+
+impl MexcSpotWs {
+
+    pub fn builder() -> MexcSpotWsBuilder {
+        MexcSpotWsBuilder::default()
+    }
+
+    pub async fn connect() -> MexcResult<(MexcSpotWs, mpsc::Receiver<SpotWsEvent>)> {
+        Self::builder().connect().await
+    }
+
+    /// Subscriptions are remembered and sent again after every reconnect
+    pub fn subscribe(&self, channels: &[SpotChannel]) -> MexcResult<()> {
+        self.subscribe_topics(channels.iter().map(|channel| channel.topic(self.format)).collect())
+    }
+
+    pub fn unsubscribe(&self, channels: &[SpotChannel]) -> MexcResult<()> {
+        self.unsubscribe_topics(channels.iter().map(|channel| channel.topic(self.format)).collect())
+    }
+
+    /// Subscribes raw topic strings, for channels not covered by `SpotChannel`
+    pub fn subscribe_topics(&self, topics: Vec<String>) -> MexcResult<()> {
+        self.send(Command::Subscribe(topics))
+    }
+
+    pub fn unsubscribe_topics(&self, topics: Vec<String>) -> MexcResult<()> {
+        self.send(Command::Unsubscribe(topics))
+    }
+
+    /// Forces a reconnect, to `url` if given
+    pub fn reconnect(&self, url: Option<String>) -> MexcResult<()> {
+        self.send(Command::Reconnect(url))
+    }
+
+    pub fn format(&self) -> WsFormat {
+        self.format
+    }
+
+    pub fn close(&self) -> MexcResult<()> {
+        self.send(Command::Close)
+    }
+
+    /// True once the connection task has stopped
+    pub fn is_closed(&self) -> bool {
+        self.commands.is_closed()
+    }
+
+    fn send(&self, command: Command) -> MexcResult<()> {
+        self.commands.send(command).map_err(|_| MexcError::ConnectionClosed)
+    }
+}
+
+struct Worker {
+    url: String,
+    ping_interval: Duration,
+    reconnect_delay: Duration,
+    topics: BTreeSet<String>,
+    commands: mpsc::UnboundedReceiver<Command>,
+    events: mpsc::Sender<SpotWsEvent>
+}
+
+impl Worker {
+
+    async fn run(mut self, mut stream: WsStream) {
+        loop {
+            if let SessionEnd::Closed = self.session(stream).await {
+                return;
+            }
+
+            if self.events.send(SpotWsEvent::Disconnected).await.is_err() {
+                return;
+            }
+
+            stream = match self.reconnect().await {
+                Some(stream) => stream,
+                None => return
+            };
+        }
+    }
+
+    /// Retries the connection until it succeeds, keeps tracking subscription changes meanwhile
+    async fn reconnect(&mut self) -> Option<WsStream> {
+        loop {
+            tokio::select! {
+                command = self.commands.recv() => match command {
+                    None | Some(Command::Close) => return None,
+                    Some(Command::Subscribe(topics)) => self.topics.extend(topics),
+                    Some(Command::Unsubscribe(topics)) => topics.iter().for_each(|topic| { self.topics.remove(topic); }),
+                    Some(Command::Reconnect(url)) => if let Some(url) = url { self.url = url }
+                },
+                _ = tokio::time::sleep(self.reconnect_delay) => {
+                    if let Ok(stream) = connect(&self.url).await {
+                        return Some(stream);
+                    }
+                }
+            }
+        }
+    }
+
+    async fn session(&mut self, stream: WsStream) -> SessionEnd {
+        let (mut sink, mut read) = stream.split();
+
+        let topics: Vec<String> = self.topics.iter().cloned().collect();
+        for msg in subscription_messages("SUBSCRIPTION", &topics) {
+            if sink.send(msg).await.is_err() {
+                return SessionEnd::Dropped;
+            }
+        }
+
+        if self.events.send(SpotWsEvent::Connected).await.is_err() {
+            return SessionEnd::Closed;
+        }
+
+        let mut ping = tokio::time::interval_at(tokio::time::Instant::now() + self.ping_interval, self.ping_interval);
+
+        loop {
+            tokio::select! {
+                command = self.commands.recv() => {
+                    let msgs = match command {
+                        None | Some(Command::Close) => {
+                            let _ = sink.send(Message::Close(None)).await;
+                            return SessionEnd::Closed;
+                        },
+                        Some(Command::Reconnect(url)) => {
+                            if let Some(url) = url {
+                                self.url = url;
+                            }
+                            let _ = sink.send(Message::Close(None)).await;
+                            return SessionEnd::Dropped;
+                        },
+                        Some(Command::Subscribe(topics)) => {
+                            self.topics.extend(topics.iter().cloned());
+                            subscription_messages("SUBSCRIPTION", &topics)
+                        },
+                        Some(Command::Unsubscribe(topics)) => {
+                            topics.iter().for_each(|topic| { self.topics.remove(topic); });
+                            subscription_messages("UNSUBSCRIPTION", &topics)
+                        }
+                    };
+
+                    for msg in msgs {
+                        if sink.send(msg).await.is_err() {
+                            return SessionEnd::Dropped;
+                        }
+                    }
+                },
+                _ = ping.tick() => {
+                    if sink.send(Message::Text(json!({"method": "PING"}).to_string())).await.is_err() {
+                        return SessionEnd::Dropped;
+                    }
+                },
+                msg = read.next() => match msg {
+                    Some(Ok(Message::Text(text))) => {
+                        if let Some(event) = decode_spot_message(&text) {
+                            if self.events.send(event).await.is_err() {
+                                return SessionEnd::Closed;
+                            }
+                        }
+                    },
+                    Some(Ok(Message::Binary(buf))) => {
+                        if let Ok(Some(event)) = decode_spot_binary(&buf) {
+                            if self.events.send(event).await.is_err() {
+                                return SessionEnd::Closed;
+                            }
+                        }
+                    },
+                    // pings are answered by tungstenite on the next read
+                    Some(Ok(Message::Close(_))) | Some(Err(_)) | None => return SessionEnd::Dropped,
+                    Some(Ok(_)) => {}
+                }
+            }
+        }
+    }
+}
+
+fn subscription_messages(method: &str, topics: &[String]) -> Vec<Message> {
+    topics
+        .chunks(MAX_TOPICS_PER_REQUEST)
+        .map(|chunk| Message::Text(json!({"method": method, "params": chunk}).to_string()))
+        .collect()
+}
+
+/// Decodes a json frame, returns `None` for pongs and frames on unknown channels
+pub(crate) fn decode_spot_message(text: &str) -> Option<SpotWsEvent> {
+    if let Ok(frame) = serde_json::from_str::<PushFrame>(text) {
+        return decode_push(frame);
+    }
+
+    match serde_json::from_str::<AckFrame>(text) {
+        Ok(ack) if ack.msg != "PONG" => Some(SpotWsEvent::Ack { id: ack.id, code: ack.code, msg: ack.msg }),
+        _ => None
+    }
+}
+
+fn decode_push(frame: PushFrame) -> Option<SpotWsEvent> {
+    fn push<T>(frame: &PushFrame, data: T) -> SpotPush<T> {
+        SpotPush {
+            channel: frame.c.clone(),
+            symbol: frame.s.clone(),
+            timestamp: frame.t,
+            data
+        }
+    }
+
+    let event = frame.c.split('@').nth(1)?;
+
+    match event {
+        "public.deals.v3.api" => {
+            let deals: WsDeals = serde_json::from_value(frame.d.clone()).ok()?;
+            Some(SpotWsEvent::Deals(push(&frame, deals.deals)))
+        },
+        "public.kline.v3.api" => {
+            let kline: WsKlineData = serde_json::from_value(frame.d.clone()).ok()?;
+            Some(SpotWsEvent::Kline(push(&frame, kline.k)))
+        },
+        "public.increase.depth.v3.api" => {
+            let depth: WsDepth = serde_json::from_value(frame.d.clone()).ok()?;
+            Some(SpotWsEvent::IncreaseDepth(push(&frame, depth)))
+        },
+        "public.limit.depth.v3.api" => {
+            let depth: WsDepth = serde_json::from_value(frame.d.clone()).ok()?;
+            Some(SpotWsEvent::LimitDepth(push(&frame, depth)))
+        },
+        "public.bookTicker.v3.api" => {
+            let ticker: WsBookTicker = serde_json::from_value(frame.d.clone()).ok()?;
+            Some(SpotWsEvent::BookTicker(push(&frame, ticker)))
+        },
+        "private.account.v3.api" => {
+            let update: WsAccountUpdate = serde_json::from_value(frame.d.clone()).ok()?;
+            Some(SpotWsEvent::AccountUpdate(push(&frame, update)))
+        },
+        "private.orders.v3.api" => {
+            let update: WsOrderUpdate = serde_json::from_value(frame.d.clone()).ok()?;
+            Some(SpotWsEvent::OrderUpdate(push(&frame, update)))
+        },
+        "private.deals.v3.api" => {
+            let deal: WsPrivateDeal = serde_json::from_value(frame.d.clone()).ok()?;
+            Some(SpotWsEvent::PrivateDeal(push(&frame, deal)))
+        },
+        _ => None
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::market::Level;
+    use tokio::net::TcpListener;
+
+    #[test]
+    pub fn test_decode_deals() {
+        let msg = r#"{"c":"spot@public.deals.v3.api@BTCUSDT","d":{"deals":[{"S":2,"p":"20233.84","t":1678245511964,"v":"0.001028"}],"e":"spot@public.deals.v3.api"},"s":"BTCUSDT","t":1678245512128}"#;
+
+        let Some(SpotWsEvent::Deals(push)) = decode_spot_message(msg) else { panic!("expected deals") };
+        assert_eq!(push.symbol, "BTCUSDT");
+        assert_eq!(push.data[0].price, 20233.84);
+        assert_eq!(push.data[0].side(), crate::orders::OrderSide::SELL);
+    }
+
+    #[test]
+    pub fn test_decode_increase_depth() {
+        let msg = r#"{"c":"spot@public.increase.depth.v3.api@BTCUSDT","d":{"asks":[{"p":"20290.89","v":"0.00000000"}],"e":"spot@public.increase.depth.v3.api","r":"3407459756"},"s":"BTCUSDT","t":1661932660144}"#;
+
+        let Some(SpotWsEvent::IncreaseDepth(push)) = decode_spot_message(msg) else { panic!("expected depth") };
+        assert_eq!(push.data.version, 3407459756);
+        assert_eq!(push.data.from_version, None);
+        assert_eq!(push.data.asks, vec![Level { px: 20290.89, sz: 0.0 }]);
+        assert!(push.data.bids.is_empty());
+    }
+
+    #[test]
+    pub fn test_decode_kline_and_book_ticker() {
+        let msg = r#"{"c":"spot@public.kline.v3.api@BTCUSDT@Min15","d":{"k":{"T":1661931900,"a":29043.48804658,"c":20279.43,"h":20284.93,"i":"Min15","l":20277.52,"o":20284.93,"t":1661931000,"v":1.43211},"e":"spot@public.kline.v3.api"},"s":"BTCUSDT","t":1661931016878}"#;
+        let Some(SpotWsEvent::Kline(push)) = decode_spot_message(msg) else { panic!("expected kline") };
+        assert_eq!(push.data.window_start, 1661931000);
+        assert_eq!(push.data.close, 20279.43);
+
+        let msg = r#"{"c":"spot@public.bookTicker.v3.api@BTCUSDT","d":{"A":"34.3","B":"6.8","a":"20259.23","b":"20258.94"},"s":"BTCUSDT","t":1661932660144}"#;
+        let Some(SpotWsEvent::BookTicker(push)) = decode_spot_message(msg) else { panic!("expected book ticker") };
+        assert_eq!(push.data.bid_price, 20258.94);
+        assert_eq!(push.data.ask_qty, 34.3);
+
+        assert!(decode_spot_message(r#"{"id":0,"code":0,"msg":"PONG"}"#).is_none());
+    }
+
+    #[test]
+    pub fn test_decode_private_frames() {
+        let msg = r#"{"c":"spot@private.account.v3.api","d":{"a":"USDT","c":1678185928428,"f":"302.185113007893322435","fd":"-4.990689704","l":"4.990689704","ld":"4.990689704","o":"ENTRUST_PLACE"},"t":1678185928435}"#;
+        let Some(SpotWsEvent::AccountUpdate(push)) = decode_spot_message(msg) else { panic!("expected account update") };
+        assert_eq!(push.data.asset, "USDT");
+        assert_eq!(push.data.free_change, -4.990689704);
+        assert_eq!(push.data.change_type, "ENTRUST_PLACE");
+
+        let msg = r#"{"c":"spot@private.deals.v3.api","d":{"p":"1.804","v":"0.01","a":"0.01804","S":1,"T":1678174206678,"t":"5bbb6ad8b4474570b155610e3960cd","c":"","i":"2dd9d8e0a2a847aab1de5ac5e6de8ef1","m":0,"st":0,"n":"0.000018","N":"USDT"},"s":"MXUSDT","t":1678174206681}"#;
+        let Some(SpotWsEvent::PrivateDeal(push)) = decode_spot_message(msg) else { panic!("expected private deal") };
+        assert_eq!(push.symbol, "MXUSDT");
+        assert_eq!(push.data.side(), crate::orders::OrderSide::BUY);
+        assert_eq!(push.data.fee, 0.000018);
+        assert!(!push.data.is_self_trade);
+
+        let msg = r#"{"c":"spot@private.orders.v3.api","d":{"A":"8.0","O":1661938138000,"S":2,"V":"10","a":"8","c":"","i":"e03a5c7441e44ed899466a7140b71391","m":0,"o":100,"p":"0.8","s":3,"v":"10"},"s":"MXUSDT","t":1661938138193}"#;
+        let Some(SpotWsEvent::OrderUpdate(push)) = decode_spot_message(msg) else { panic!("expected order update") };
+        assert_eq!(push.data.side(), crate::orders::OrderSide::SELL);
+        assert_eq!(push.data.order_type, crate::orders::OrderType::UNKNOWN);
+        assert_eq!(push.data.status, crate::orders::OrderStatus::PARTIALLY_FILLED);
+    }
+
+    #[tokio::test]
+    pub async fn test_resubscribes_after_reconnect() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("ws://{}", listener.local_addr().unwrap());
+
+        let server = tokio::spawn(async move {
+            let mut received = vec![];
+
+            // first connection: read the subscription, then drop the socket
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+            if let Some(Ok(Message::Text(text))) = ws.next().await {
+                received.push(text);
+            }
+            drop(ws);
+
+            // second connection: expect the replayed subscription and push a frame
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_async(socket).await.unwrap();
+            if let Some(Ok(Message::Text(text))) = ws.next().await {
+                received.push(text);
+            }
+            let push = r#"{"c":"spot@public.bookTicker.v3.api@BTCUSDT","d":{"A":"1","B":"2","a":"101","b":"100"},"s":"BTCUSDT","t":1}"#;
+            ws.send(Message::Text(push.into())).await.unwrap();
+            let _ = ws.next().await;
+            received
+        });
+
+        let (ws, mut events) = MexcSpotWs::builder()
+            .url(url)
+            .format(WsFormat::Json)
+            .reconnect_delay(Duration::from_millis(10))
+            .connect().await.unwrap();
+
+        assert!(matches!(events.recv().await, Some(SpotWsEvent::Connected)));
+        ws.subscribe(&[SpotChannel::BookTicker { symbol: "BTCUSDT".into() }]).unwrap();
+
+        assert!(matches!(events.recv().await, Some(SpotWsEvent::Disconnected)));
+        assert!(matches!(events.recv().await, Some(SpotWsEvent::Connected)));
+        let Some(SpotWsEvent::BookTicker(push)) = events.recv().await else { panic!("expected book ticker") };
+        assert_eq!(push.data.bid_price, 100.0);
+
+        ws.close().unwrap();
+        let received = server.await.unwrap();
+        let expected = r#"{"method":"SUBSCRIPTION","params":["spot@public.bookTicker.v3.api@BTCUSDT"]}"#;
+        assert_eq!(received, vec![expected, expected]);
+    }
+}
@@ -0,0 +1,660 @@
+/*
+
+Hand written decoders for the protobuf push frames of the spot websocket.
+
+Field numbers follow https://github.com/mexcdevelop/websocket-proto
+
+ */
+
+use crate::market::Level;
+use super::structures::{SpotPush, SpotWsEvent, WsAccountUpdate, WsBookTicker, WsDeal, WsDepth, WsKline, WsOrderUpdate, WsPrivateDeal, order_status_from_code, order_type_from_code};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ProtoError(pub String);
+
+impl std::fmt::Display for ProtoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "protobuf decode error: {}", self.0)
+    }
+}
+
+impl std::error::Error for ProtoError {}
+
+pub enum WireValue<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(&'a [u8]),
+    Fixed32(u32)
+}
+
+impl<'a> WireValue<'a> {
+    fn as_string(&self) -> Result<String, ProtoError> {
+        match self {
+            WireValue::Bytes(bytes) => String::from_utf8(bytes.to_vec()).map_err(|err| ProtoError(err.to_string())),
+            _ => Err(ProtoError("expected length delimited field".into()))
+        }
+    }
+
+    fn as_bytes(&self) -> Result<&'a [u8], ProtoError> {
+        match self {
+            WireValue::Bytes(bytes) => Ok(bytes),
+            _ => Err(ProtoError("expected length delimited field".into()))
+        }
+    }
+
+    fn as_u64(&self) -> Result<u64, ProtoError> {
+        match self {
+            WireValue::Varint(v) | WireValue::Fixed64(v) => Ok(*v),
+            WireValue::Fixed32(v) => Ok(*v as u64),
+            _ => Err(ProtoError("expected numeric field".into()))
+        }
+    }
+
+    fn as_i64(&self) -> Result<i64, ProtoError> {
+        self.as_u64().map(|v| v as i64)
+    }
+
+    fn as_i32(&self) -> Result<i32, ProtoError> {
+        self.as_u64().map(|v| v as i32)
+    }
+
+    fn as_bool(&self) -> Result<bool, ProtoError> {
+        self.as_u64().map(|v| v != 0)
+    }
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize
+}
+
+impl<'a> Reader<'a> {
+
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    fn varint(&mut self) -> Result<u64, ProtoError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = *self.buf.get(self.pos).ok_or_else(|| ProtoError("truncated varint".into()))?;
+            self.pos += 1;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(ProtoError("varint too long".into()))
+    }
+
+    fn take(&mut self, len: usize) -> Result<&'a [u8], ProtoError> {
+        let end = self.pos.checked_add(len).filter(|end| *end <= self.buf.len()).ok_or_else(|| ProtoError("truncated field".into()))?;
+        let bytes = &self.buf[self.pos..end];
+        self.pos = end;
+        Ok(bytes)
+    }
+
+    fn field(&mut self) -> Option<Result<(u32, WireValue<'a>), ProtoError>> {
+        if self.pos >= self.buf.len() {
+            return None;
+        }
+        Some(self.read_field())
+    }
+
+    fn read_field(&mut self) -> Result<(u32, WireValue<'a>), ProtoError> {
+        let key = self.varint()?;
+        let field = (key >> 3) as u32;
+        let value = match key & 0x7 {
+            0 => WireValue::Varint(self.varint()?),
+            1 => WireValue::Fixed64(u64::from_le_bytes(self.take(8)?.try_into().unwrap())),
+            2 => {
+                let len = self.varint()? as usize;
+                WireValue::Bytes(self.take(len)?)
+            },
+            5 => WireValue::Fixed32(u32::from_le_bytes(self.take(4)?.try_into().unwrap())),
+            wire_type => return Err(ProtoError(format!("unsupported wire type {wire_type}")))
+        };
+        Ok((field, value))
+    }
+}
+
+pub trait ProtoMessage: Default {
+    fn merge_field(&mut self, field: u32, value: WireValue) -> Result<(), ProtoError>;
+
+    fn decode(buf: &[u8]) -> Result<Self, ProtoError> {
+        let mut msg = Self::default();
+        let mut reader = Reader::new(buf);
+        while let Some(field) = reader.field() {
+            let (field, value) = field?;
+            msg.merge_field(field, value)?;
+        }
+        Ok(msg)
+    }
+}
+
+fn parse_f64(s: &str) -> Result<f64, ProtoError> {
+    if s.is_empty() {
+        return Ok(0.0);
+    }
+    s.parse().map_err(|_| ProtoError(format!("invalid decimal {s}")))
+}
+
+fn parse_u64(s: &str) -> Result<u64, ProtoError> {
+    s.parse().map_err(|_| ProtoError(format!("invalid integer {s}")))
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PublicDealItem {
+    pub price: String,
+    pub quantity: String,
+    pub trade_type: i32,
+    pub time: i64
+}
+
+impl ProtoMessage for PublicDealItem {
+    fn merge_field(&mut self, field: u32, value: WireValue) -> Result<(), ProtoError> {
+        match field {
+            1 => self.price = value.as_string()?,
+            2 => self.quantity = value.as_string()?,
+            3 => self.trade_type = value.as_i32()?,
+            4 => self.time = value.as_i64()?,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// PublicDealsV3Api and PublicAggreDealsV3Api share their layout
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PublicDeals {
+    pub deals: Vec<PublicDealItem>,
+    pub event_type: String
+}
+
+impl ProtoMessage for PublicDeals {
+    fn merge_field(&mut self, field: u32, value: WireValue) -> Result<(), ProtoError> {
+        match field {
+            1 => self.deals.push(PublicDealItem::decode(value.as_bytes()?)?),
+            2 => self.event_type = value.as_string()?,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DepthItem {
+    pub price: String,
+    pub quantity: String
+}
+
+impl ProtoMessage for DepthItem {
+    fn merge_field(&mut self, field: u32, value: WireValue) -> Result<(), ProtoError> {
+        match field {
+            1 => self.price = value.as_string()?,
+            2 => self.quantity = value.as_string()?,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// PublicIncreaseDepthsV3Api and PublicLimitDepthsV3Api: single `version`
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PublicDepths {
+    pub asks: Vec<DepthItem>,
+    pub bids: Vec<DepthItem>,
+    pub event_type: String,
+    pub version: String
+}
+
+impl ProtoMessage for PublicDepths {
+    fn merge_field(&mut self, field: u32, value: WireValue) -> Result<(), ProtoError> {
+        match field {
+            1 => self.asks.push(DepthItem::decode(value.as_bytes()?)?),
+            2 => self.bids.push(DepthItem::decode(value.as_bytes()?)?),
+            3 => self.event_type = value.as_string()?,
+            4 => self.version = value.as_string()?,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// PublicAggreDepthsV3Api: covers the version range `from_version..=to_version`
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PublicAggreDepths {
+    pub asks: Vec<DepthItem>,
+    pub bids: Vec<DepthItem>,
+    pub event_type: String,
+    pub from_version: String,
+    pub to_version: String
+}
+
+impl ProtoMessage for PublicAggreDepths {
+    fn merge_field(&mut self, field: u32, value: WireValue) -> Result<(), ProtoError> {
+        match field {
+            1 => self.asks.push(DepthItem::decode(value.as_bytes()?)?),
+            2 => self.bids.push(DepthItem::decode(value.as_bytes()?)?),
+            3 => self.event_type = value.as_string()?,
+            4 => self.from_version = value.as_string()?,
+            5 => self.to_version = value.as_string()?,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+/// PublicBookTickerV3Api and PublicAggreBookTickerV3Api share their layout
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PublicBookTicker {
+    pub bid_price: String,
+    pub bid_quantity: String,
+    pub ask_price: String,
+    pub ask_quantity: String
+}
+
+impl ProtoMessage for PublicBookTicker {
+    fn merge_field(&mut self, field: u32, value: WireValue) -> Result<(), ProtoError> {
+        match field {
+            1 => self.bid_price = value.as_string()?,
+            2 => self.bid_quantity = value.as_string()?,
+            3 => self.ask_price = value.as_string()?,
+            4 => self.ask_quantity = value.as_string()?,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PublicSpotKline {
+    pub interval: String,
+    pub window_start: i64,
+    pub opening_price: String,
+    pub closing_price: String,
+    pub highest_price: String,
+    pub lowest_price: String,
+    pub volume: String,
+    pub amount: String,
+    pub window_end: i64
+}
+
+impl ProtoMessage for PublicSpotKline {
+    fn merge_field(&mut self, field: u32, value: WireValue) -> Result<(), ProtoError> {
+        match field {
+            1 => self.interval = value.as_string()?,
+            2 => self.window_start = value.as_i64()?,
+            3 => self.opening_price = value.as_string()?,
+            4 => self.closing_price = value.as_string()?,
+            5 => self.highest_price = value.as_string()?,
+            6 => self.lowest_price = value.as_string()?,
+            7 => self.volume = value.as_string()?,
+            8 => self.amount = value.as_string()?,
+            9 => self.window_end = value.as_i64()?,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PrivateOrders {
+    pub id: String,
+    pub client_id: String,
+    pub price: String,
+    pub quantity: String,
+    pub amount: String,
+    pub avg_price: String,
+    pub order_type: i32,
+    pub trade_type: i32,
+    pub is_maker: bool,
+    pub remain_amount: String,
+    pub remain_quantity: String,
+    pub cumulative_quantity: String,
+    pub cumulative_amount: String,
+    pub status: i32,
+    pub create_time: i64
+}
+
+impl ProtoMessage for PrivateOrders {
+    fn merge_field(&mut self, field: u32, value: WireValue) -> Result<(), ProtoError> {
+        match field {
+            1 => self.id = value.as_string()?,
+            2 => self.client_id = value.as_string()?,
+            3 => self.price = value.as_string()?,
+            4 => self.quantity = value.as_string()?,
+            5 => self.amount = value.as_string()?,
+            6 => self.avg_price = value.as_string()?,
+            7 => self.order_type = value.as_i32()?,
+            8 => self.trade_type = value.as_i32()?,
+            9 => self.is_maker = value.as_bool()?,
+            10 => self.remain_amount = value.as_string()?,
+            11 => self.remain_quantity = value.as_string()?,
+            13 => self.cumulative_quantity = value.as_string()?,
+            14 => self.cumulative_amount = value.as_string()?,
+            15 => self.status = value.as_i32()?,
+            16 => self.create_time = value.as_i64()?,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PrivateDeals {
+    pub price: String,
+    pub quantity: String,
+    pub amount: String,
+    pub trade_type: i32,
+    pub is_maker: bool,
+    pub is_self_trade: bool,
+    pub trade_id: String,
+    pub client_order_id: String,
+    pub order_id: String,
+    pub fee_amount: String,
+    pub fee_currency: String,
+    pub time: i64
+}
+
+impl ProtoMessage for PrivateDeals {
+    fn merge_field(&mut self, field: u32, value: WireValue) -> Result<(), ProtoError> {
+        match field {
+            1 => self.price = value.as_string()?,
+            2 => self.quantity = value.as_string()?,
+            3 => self.amount = value.as_string()?,
+            4 => self.trade_type = value.as_i32()?,
+            5 => self.is_maker = value.as_bool()?,
+            6 => self.is_self_trade = value.as_bool()?,
+            7 => self.trade_id = value.as_string()?,
+            8 => self.client_order_id = value.as_string()?,
+            9 => self.order_id = value.as_string()?,
+            10 => self.fee_amount = value.as_string()?,
+            11 => self.fee_currency = value.as_string()?,
+            12 => self.time = value.as_i64()?,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PrivateAccount {
+    pub vcoin_name: String,
+    pub coin_id: String,
+    pub balance_amount: String,
+    pub balance_amount_change: String,
+    pub frozen_amount: String,
+    pub frozen_amount_change: String,
+    pub change_type: String,
+    pub time: i64
+}
+
+impl ProtoMessage for PrivateAccount {
+    fn merge_field(&mut self, field: u32, value: WireValue) -> Result<(), ProtoError> {
+        match field {
+            1 => self.vcoin_name = value.as_string()?,
+            2 => self.coin_id = value.as_string()?,
+            3 => self.balance_amount = value.as_string()?,
+            4 => self.balance_amount_change = value.as_string()?,
+            5 => self.frozen_amount = value.as_string()?,
+            6 => self.frozen_amount_change = value.as_string()?,
+            7 => self.change_type = value.as_string()?,
+            8 => self.time = value.as_i64()?,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PushBody {
+    PublicDeals(PublicDeals),
+    PublicIncreaseDepths(PublicDepths),
+    PublicLimitDepths(PublicDepths),
+    PublicBookTicker(PublicBookTicker),
+    PublicSpotKline(PublicSpotKline),
+    PublicAggreDepths(PublicAggreDepths),
+    PublicAggreDeals(PublicDeals),
+    PublicAggreBookTicker(PublicBookTicker),
+    PrivateOrders(PrivateOrders),
+    PrivateDeals(PrivateDeals),
+    PrivateAccount(PrivateAccount)
+}
+
+/// Envelope of every protobuf push
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PushDataV3ApiWrapper {
+    pub channel: String,
+    pub symbol: String,
+    pub symbol_id: String,
+    pub create_time: i64,
+    pub send_time: i64,
+    pub body: Option<PushBody>
+}
+
+impl ProtoMessage for PushDataV3ApiWrapper {
+    fn merge_field(&mut self, field: u32, value: WireValue) -> Result<(), ProtoError> {
+        match field {
+            1 => self.channel = value.as_string()?,
+            3 => self.symbol = value.as_string()?,
+            4 => self.symbol_id = value.as_string()?,
+            5 => self.create_time = value.as_i64()?,
+            6 => self.send_time = value.as_i64()?,
+            301 => self.body = Some(PushBody::PublicDeals(PublicDeals::decode(value.as_bytes()?)?)),
+            302 => self.body = Some(PushBody::PublicIncreaseDepths(PublicDepths::decode(value.as_bytes()?)?)),
+            303 => self.body = Some(PushBody::PublicLimitDepths(PublicDepths::decode(value.as_bytes()?)?)),
+            304 => self.body = Some(PushBody::PrivateOrders(PrivateOrders::decode(value.as_bytes()?)?)),
+            305 => self.body = Some(PushBody::PublicBookTicker(PublicBookTicker::decode(value.as_bytes()?)?)),
+            306 => self.body = Some(PushBody::PrivateDeals(PrivateDeals::decode(value.as_bytes()?)?)),
+            307 => self.body = Some(PushBody::PrivateAccount(PrivateAccount::decode(value.as_bytes()?)?)),
+            308 => self.body = Some(PushBody::PublicSpotKline(PublicSpotKline::decode(value.as_bytes()?)?)),
+            313 => self.body = Some(PushBody::PublicAggreDepths(PublicAggreDepths::decode(value.as_bytes()?)?)),
+            314 => self.body = Some(PushBody::PublicAggreDeals(PublicDeals::decode(value.as_bytes()?)?)),
+            315 => self.body = Some(PushBody::PublicAggreBookTicker(PublicBookTicker::decode(value.as_bytes()?)?)),
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
+fn levels(items: &[DepthItem]) -> Result<Vec<Level>, ProtoError> {
+    items.iter().map(|item| Ok(Level { px: parse_f64(&item.price)?, sz: parse_f64(&item.quantity)? })).collect()
+}
+
+fn deals(deals: &PublicDeals) -> Result<Vec<WsDeal>, ProtoError> {
+    deals.deals.iter().map(|deal| Ok(WsDeal {
+        price: parse_f64(&deal.price)?,
+        quantity: parse_f64(&deal.quantity)?,
+        trade_type: deal.trade_type as u8,
+        time: deal.time as u128
+    })).collect()
+}
+
+fn book_ticker(ticker: &PublicBookTicker) -> Result<WsBookTicker, ProtoError> {
+    Ok(WsBookTicker {
+        bid_price: parse_f64(&ticker.bid_price)?,
+        bid_qty: parse_f64(&ticker.bid_quantity)?,
+        ask_price: parse_f64(&ticker.ask_price)?,
+        ask_qty: parse_f64(&ticker.ask_quantity)?
+    })
+}
+
+/// Decodes a binary frame into the same events the json frames produce, `Ok(None)` for bodies without an event
+pub fn decode_spot_binary(buf: &[u8]) -> Result<Option<SpotWsEvent>, ProtoError> {
+    let wrapper = PushDataV3ApiWrapper::decode(buf)?;
+
+    let Some(body) = &wrapper.body else {
+        return Ok(None);
+    };
+
+    fn push<T>(wrapper: &PushDataV3ApiWrapper, data: T) -> SpotPush<T> {
+        SpotPush {
+            channel: wrapper.channel.clone(),
+            symbol: wrapper.symbol.clone(),
+            timestamp: if wrapper.send_time > 0 { wrapper.send_time } else { wrapper.create_time } as u128,
+            data
+        }
+    }
+
+    let event = match body {
+        PushBody::PublicDeals(d) | PushBody::PublicAggreDeals(d) => SpotWsEvent::Deals(push(&wrapper, deals(d)?)),
+        PushBody::PublicIncreaseDepths(d) => SpotWsEvent::IncreaseDepth(push(&wrapper, WsDepth {
+            asks: levels(&d.asks)?,
+            bids: levels(&d.bids)?,
+            from_version: None,
+            version: parse_u64(&d.version)?
+        })),
+        PushBody::PublicAggreDepths(d) => SpotWsEvent::IncreaseDepth(push(&wrapper, WsDepth {
+            asks: levels(&d.asks)?,
+            bids: levels(&d.bids)?,
+            from_version: Some(parse_u64(&d.from_version)?),
+            version: parse_u64(&d.to_version)?
+        })),
+        PushBody::PublicLimitDepths(d) => SpotWsEvent::LimitDepth(push(&wrapper, WsDepth {
+            asks: levels(&d.asks)?,
+            bids: levels(&d.bids)?,
+            from_version: None,
+            version: parse_u64(&d.version)?
+        })),
+        PushBody::PublicBookTicker(t) | PushBody::PublicAggreBookTicker(t) => SpotWsEvent::BookTicker(push(&wrapper, book_ticker(t)?)),
+        PushBody::PublicSpotKline(k) => SpotWsEvent::Kline(push(&wrapper, WsKline {
+            interval: k.interval.clone(),
+            window_start: k.window_start as u64,
+            window_end: k.window_end as u64,
+            open: parse_f64(&k.opening_price)?,
+            close: parse_f64(&k.closing_price)?,
+            high: parse_f64(&k.highest_price)?,
+            low: parse_f64(&k.lowest_price)?,
+            volume: parse_f64(&k.volume)?,
+            amount: parse_f64(&k.amount)?
+        })),
+        PushBody::PrivateOrders(o) => SpotWsEvent::OrderUpdate(push(&wrapper, WsOrderUpdate {
+            order_id: o.id.clone(),
+            client_order_id: o.client_id.clone(),
+            price: parse_f64(&o.price)?,
+            quantity: parse_f64(&o.quantity)?,
+            amount: parse_f64(&o.amount)?,
+            avg_price: parse_f64(&o.avg_price)?,
+            order_type: order_type_from_code(o.order_type),
+            trade_type: o.trade_type as u8,
+            is_maker: o.is_maker,
+            remain_amount: parse_f64(&o.remain_amount)?,
+            remain_quantity: parse_f64(&o.remain_quantity)?,
+            cumulative_quantity: parse_f64(&o.cumulative_quantity)?,
+            cumulative_amount: parse_f64(&o.cumulative_amount)?,
+            status: order_status_from_code(o.status),
+            create_time: o.create_time as u128
+        })),
+        PushBody::PrivateDeals(d) => SpotWsEvent::PrivateDeal(push(&wrapper, WsPrivateDeal {
+            price: parse_f64(&d.price)?,
+            quantity: parse_f64(&d.quantity)?,
+            amount: parse_f64(&d.amount)?,
+            trade_type: d.trade_type as u8,
+            is_maker: d.is_maker,
+            is_self_trade: d.is_self_trade,
+            trade_id: d.trade_id.clone(),
+            client_order_id: d.client_order_id.clone(),
+            order_id: d.order_id.clone(),
+            fee: parse_f64(&d.fee_amount)?,
+            fee_currency: d.fee_currency.clone(),
+            time: d.time as u128
+        })),
+        PushBody::PrivateAccount(a) => SpotWsEvent::AccountUpdate(push(&wrapper, WsAccountUpdate {
+            asset: a.vcoin_name.clone(),
+            time: a.time as u128,
+            free: parse_f64(&a.balance_amount)?,
+            free_change: parse_f64(&a.balance_amount_change)?,
+            locked: parse_f64(&a.frozen_amount)?,
+            locked_change: parse_f64(&a.frozen_amount_change)?,
+            change_type: a.change_type.clone()
+        }))
+    };
+
+    Ok(Some(event))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn test_decode_aggre_deals_fixture() {
+        let frame = include_bytes!("fixtures/aggre_deals.pb");
+
+        let Some(SpotWsEvent::Deals(push)) = decode_spot_binary(frame).unwrap() else { panic!("expected deals") };
+        assert_eq!(push.channel, "spot@public.aggre.deals.v3.api.pb@100ms@BTCUSDT");
+        assert_eq!(push.symbol, "BTCUSDT");
+        assert_eq!(push.data.len(), 2);
+        assert_eq!(push.data[0].price, 93220.0);
+        assert_eq!(push.data[0].quantity, 0.04438243);
+        assert_eq!(push.data[0].side(), crate::orders::OrderSide::SELL);
+        assert_eq!(push.data[1].time, 1736409765051);
+    }
+
+    #[test]
+    pub fn test_decode_aggre_depth_fixture() {
+        let frame = include_bytes!("fixtures/aggre_depth.pb");
+
+        let Some(SpotWsEvent::IncreaseDepth(push)) = decode_spot_binary(frame).unwrap() else { panic!("expected depth") };
+        assert_eq!(push.data.from_version, Some(10589632359));
+        assert_eq!(push.data.version, 10589632359);
+        assert_eq!(push.data.asks, vec![Level { px: 92877.58, sz: 0.0 }]);
+        assert!(push.data.bids.is_empty());
+    }
+
+    #[test]
+    pub fn test_decode_limit_depth_and_kline_fixtures() {
+        let frame = include_bytes!("fixtures/limit_depth.pb");
+        let Some(SpotWsEvent::LimitDepth(push)) = decode_spot_binary(frame).unwrap() else { panic!("expected depth") };
+        assert_eq!(push.data.version, 36913293511);
+        assert_eq!(push.data.bids[0], Level { px: 93180.18, sz: 0.21976424 });
+        assert_eq!(push.data.asks.len(), 2);
+
+        let frame = include_bytes!("fixtures/kline.pb");
+        let Some(SpotWsEvent::Kline(push)) = decode_spot_binary(frame).unwrap() else { panic!("expected kline") };
+        assert_eq!(push.data.interval, "Min15");
+        assert_eq!(push.data.window_start, 1736410500);
+        assert_eq!(push.data.high, 93302.02);
+    }
+
+    #[test]
+    pub fn test_decode_book_ticker_fixture() {
+        let frame = include_bytes!("fixtures/aggre_book_ticker.pb");
+        let Some(SpotWsEvent::BookTicker(push)) = decode_spot_binary(frame).unwrap() else { panic!("expected book ticker") };
+        assert_eq!(push.data.bid_price, 93387.28);
+        assert_eq!(push.data.ask_qty, 1.94039022);
+    }
+
+    #[test]
+    pub fn test_decode_private_fixtures() {
+        let frame = include_bytes!("fixtures/private_orders.pb");
+        let Some(SpotWsEvent::OrderUpdate(push)) = decode_spot_binary(frame).unwrap() else { panic!("expected order update") };
+        assert_eq!(push.symbol, "MXUSDT");
+        assert_eq!(push.data.order_id, "C02__413321238354677760043");
+        assert_eq!(push.data.price, 0.5);
+        assert_eq!(push.data.remain_quantity, 10.0);
+        assert_eq!(push.data.status, crate::orders::OrderStatus::NEW);
+        assert_eq!(push.data.order_type, crate::orders::OrderType::LIMIT);
+        assert_eq!(push.data.side(), crate::orders::OrderSide::BUY);
+
+        let frame = include_bytes!("fixtures/private_deals.pb");
+        let Some(SpotWsEvent::PrivateDeal(push)) = decode_spot_binary(frame).unwrap() else { panic!("expected private deal") };
+        assert_eq!(push.data.trade_id, "505979017439002624X1");
+        assert_eq!(push.data.fee, 0.000018);
+        assert!(push.data.is_maker);
+
+        let frame = include_bytes!("fixtures/private_account.pb");
+        let Some(SpotWsEvent::AccountUpdate(push)) = decode_spot_binary(frame).unwrap() else { panic!("expected account update") };
+        assert_eq!(push.data.asset, "USDT");
+        assert_eq!(push.data.free, 21.94210356004384);
+        assert_eq!(push.data.change_type, "CONTRACT_TRANSFER");
+    }
+
+    #[test]
+    pub fn test_truncated_frame() {
+        let frame = include_bytes!("fixtures/aggre_deals.pb");
+        assert!(decode_spot_binary(&frame[..frame.len() - 3]).is_err());
+    }
+}
@@ -0,0 +1,349 @@
+use serde::Deserialize;
+use crate::market::Level;
+use serde::Deserializer;
+use crate::orders::{OrderSide, OrderStatus, OrderType};
+use crate::utils::{parse_bool, parse_string_to_f64, parse_string_to_u64};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum WsKlineInterval {
+    Min1,
+    Min5,
+    Min15,
+    Min30,
+    Min60,
+    Hour4,
+    Hour8,
+    Day1,
+    Week1,
+    Month1
+}
+impl std::fmt::Display for WsKlineInterval {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WsKlineInterval::Min1 => write!(f, "Min1"),
+            WsKlineInterval::Min5 => write!(f, "Min5"),
+            WsKlineInterval::Min15 => write!(f, "Min15"),
+            WsKlineInterval::Min30 => write!(f, "Min30"),
+            WsKlineInterval::Min60 => write!(f, "Min60"),
+            WsKlineInterval::Hour4 => write!(f, "Hour4"),
+            WsKlineInterval::Hour8 => write!(f, "Hour8"),
+            WsKlineInterval::Day1 => write!(f, "Day1"),
+            WsKlineInterval::Week1 => write!(f, "Week1"),
+            WsKlineInterval::Month1 => write!(f, "Month1"),
+        }
+    }
+}
+
+/// Encoding of the push frames. Protobuf is the current api, json the legacy one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WsFormat {
+    #[default]
+    Protobuf,
+    Json
+}
+
+/// Aggregation window of the protobuf deals, depth and book ticker streams
+pub const PB_AGGREGATION: &str = "100ms";
+
+// https://mexcdevelop.github.io/apidocs/spot_v3_en/#public-subscription
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum SpotChannel {
+    Deals { symbol: String },
+    Kline { symbol: String, interval: WsKlineInterval },
+    IncreaseDepth { symbol: String },
+    /// Partial book of 5, 10 or 20 levels
+    LimitDepth { symbol: String, levels: u32 },
+    BookTicker { symbol: String },
+    /// Balance changes, needs a listen key connection
+    Account,
+    /// Order updates, needs a listen key connection
+    Orders,
+    /// Own fills, needs a listen key connection
+    PrivateDeals
+}
+
+impl SpotChannel {
+    pub fn topic(&self, format: WsFormat) -> String {
+        if format == WsFormat::Protobuf {
+            return match self {
+                SpotChannel::Deals { symbol } => format!("spot@public.aggre.deals.v3.api.pb@{PB_AGGREGATION}@{symbol}"),
+                SpotChannel::Kline { symbol, interval } => format!("spot@public.kline.v3.api.pb@{symbol}@{interval}"),
+                SpotChannel::IncreaseDepth { symbol } => format!("spot@public.aggre.depth.v3.api.pb@{PB_AGGREGATION}@{symbol}"),
+                SpotChannel::LimitDepth { symbol, levels } => format!("spot@public.limit.depth.v3.api.pb@{symbol}@{levels}"),
+                SpotChannel::BookTicker { symbol } => format!("spot@public.aggre.bookTicker.v3.api.pb@{PB_AGGREGATION}@{symbol}"),
+                SpotChannel::Account => "spot@private.account.v3.api.pb".to_string(),
+                SpotChannel::Orders => "spot@private.orders.v3.api.pb".to_string(),
+                SpotChannel::PrivateDeals => "spot@private.deals.v3.api.pb".to_string()
+            };
+        }
+
+        match self {
+            SpotChannel::Deals { symbol } => format!("spot@public.deals.v3.api@{symbol}"),
+            SpotChannel::Kline { symbol, interval } => format!("spot@public.kline.v3.api@{symbol}@{interval}"),
+            SpotChannel::IncreaseDepth { symbol } => format!("spot@public.increase.depth.v3.api@{symbol}"),
+            SpotChannel::LimitDepth { symbol, levels } => format!("spot@public.limit.depth.v3.api@{symbol}@{levels}"),
+            SpotChannel::BookTicker { symbol } => format!("spot@public.bookTicker.v3.api@{symbol}"),
+            SpotChannel::Account => "spot@private.account.v3.api".to_string(),
+            SpotChannel::Orders => "spot@private.orders.v3.api".to_string(),
+            SpotChannel::PrivateDeals => "spot@private.deals.v3.api".to_string()
+        }
+    }
+}
+
+/// Side of the numeric trade type in the push frames, 1 = buy, 2 = sell
+pub(crate) fn side_from_code(trade_type: u8) -> OrderSide {
+    if trade_type == 1 { OrderSide::BUY } else { OrderSide::SELL }
+}
+
+/// 1 = limit, 2 = post only, 3 = immediate or cancel, 4 = fill or kill, 5 = market
+pub(crate) fn order_type_from_code(code: i32) -> OrderType {
+    match code {
+        1 => OrderType::LIMIT,
+        2 => OrderType::LIMIT_MAKER,
+        3 => OrderType::IMMEDIATE_OR_CANCEL,
+        4 => OrderType::FILL_OR_KILL,
+        5 => OrderType::MARKET,
+        _ => OrderType::UNKNOWN
+    }
+}
+
+/// 1 = new, 2 = filled, 3 = partially filled, 4 = canceled, 5 = partially canceled
+pub(crate) fn order_status_from_code(code: i32) -> OrderStatus {
+    match code {
+        1 => OrderStatus::NEW,
+        2 => OrderStatus::FILLED,
+        3 => OrderStatus::PARTIALLY_FILLED,
+        4 => OrderStatus::CANCELED,
+        5 => OrderStatus::PARTIALLY_CANCELED,
+        _ => OrderStatus::UNKNOWN
+    }
+}
+
+fn parse_order_type_code<'de, D: Deserializer<'de>>(deserializer: D) -> Result<OrderType, D::Error> {
+    i32::deserialize(deserializer).map(order_type_from_code)
+}
+
+fn parse_order_status_code<'de, D: Deserializer<'de>>(deserializer: D) -> Result<OrderStatus, D::Error> {
+    i32::deserialize(deserializer).map(order_status_from_code)
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct WsDeal {
+    #[serde(rename = "p", deserialize_with = "parse_string_to_f64")]
+    pub price: f64,
+    #[serde(rename = "v", deserialize_with = "parse_string_to_f64")]
+    pub quantity: f64,
+    /// 1 = buy, 2 = sell
+    #[serde(rename = "S")]
+    pub trade_type: u8,
+    #[serde(rename = "t")]
+    pub time: u128
+}
+
+impl WsDeal {
+    pub fn side(&self) -> OrderSide {
+        side_from_code(self.trade_type)
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub(crate) struct WsDeals {
+    pub deals: Vec<WsDeal>
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct WsKline {
+    #[serde(rename = "i")]
+    pub interval: String,
+    /// Window start in seconds
+    #[serde(rename = "t")]
+    pub window_start: u64,
+    /// Window end in seconds
+    #[serde(rename = "T")]
+    pub window_end: u64,
+    #[serde(rename = "o", deserialize_with = "parse_string_to_f64")]
+    pub open: f64,
+    #[serde(rename = "c", deserialize_with = "parse_string_to_f64")]
+    pub close: f64,
+    #[serde(rename = "h", deserialize_with = "parse_string_to_f64")]
+    pub high: f64,
+    #[serde(rename = "l", deserialize_with = "parse_string_to_f64")]
+    pub low: f64,
+    #[serde(rename = "v", deserialize_with = "parse_string_to_f64")]
+    pub volume: f64,
+    #[serde(rename = "a", deserialize_with = "parse_string_to_f64")]
+    pub amount: f64
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub(crate) struct WsKlineData {
+    pub k: WsKline
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct WsDepth {
+    #[serde(default)]
+    pub asks: Vec<Level>,
+    #[serde(default)]
+    pub bids: Vec<Level>,
+    /// First version covered by an aggregated update, `None` when it covers `version` only
+    #[serde(skip)]
+    pub from_version: Option<u64>,
+    #[serde(rename = "r", deserialize_with = "parse_string_to_u64")]
+    pub version: u64
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct WsBookTicker {
+    #[serde(rename = "b", deserialize_with = "parse_string_to_f64")]
+    pub bid_price: f64,
+    #[serde(rename = "B", deserialize_with = "parse_string_to_f64")]
+    pub bid_qty: f64,
+    #[serde(rename = "a", deserialize_with = "parse_string_to_f64")]
+    pub ask_price: f64,
+    #[serde(rename = "A", deserialize_with = "parse_string_to_f64")]
+    pub ask_qty: f64
+}
+
+// https://mexcdevelop.github.io/apidocs/spot_v3_en/#spot-account-update
+#[derive(Deserialize, Debug, Clone)]
+pub struct WsAccountUpdate {
+    #[serde(rename = "a")]
+    pub asset: String,
+    #[serde(rename = "c")]
+    pub time: u128,
+    #[serde(rename = "f", deserialize_with = "parse_string_to_f64")]
+    pub free: f64,
+    #[serde(rename = "fd", deserialize_with = "parse_string_to_f64")]
+    pub free_change: f64,
+    #[serde(rename = "l", deserialize_with = "parse_string_to_f64")]
+    pub locked: f64,
+    #[serde(rename = "ld", deserialize_with = "parse_string_to_f64")]
+    pub locked_change: f64,
+    /// Reason of the change, e.g. ENTRUST_PLACE or CONTRACT_TRANSFER
+    #[serde(rename = "o")]
+    pub change_type: String
+}
+
+// https://mexcdevelop.github.io/apidocs/spot_v3_en/#spot-account-orders
+#[derive(Deserialize, Debug, Clone)]
+pub struct WsOrderUpdate {
+    #[serde(rename = "i")]
+    pub order_id: String,
+    #[serde(rename = "c", default)]
+    pub client_order_id: String,
+    #[serde(rename = "p", deserialize_with = "parse_string_to_f64")]
+    pub price: f64,
+    #[serde(rename = "v", deserialize_with = "parse_string_to_f64")]
+    pub quantity: f64,
+    #[serde(rename = "a", deserialize_with = "parse_string_to_f64")]
+    pub amount: f64,
+    #[serde(rename = "ap", default, deserialize_with = "parse_string_to_f64")]
+    pub avg_price: f64,
+    #[serde(rename = "o", deserialize_with = "parse_order_type_code")]
+    pub order_type: OrderType,
+    /// 1 = buy, 2 = sell
+    #[serde(rename = "S")]
+    pub trade_type: u8,
+    #[serde(rename = "m", default, deserialize_with = "parse_bool")]
+    pub is_maker: bool,
+    #[serde(rename = "A", deserialize_with = "parse_string_to_f64")]
+    pub remain_amount: f64,
+    #[serde(rename = "V", deserialize_with = "parse_string_to_f64")]
+    pub remain_quantity: f64,
+    #[serde(rename = "cv", default, deserialize_with = "parse_string_to_f64")]
+    pub cumulative_quantity: f64,
+    #[serde(rename = "ca", default, deserialize_with = "parse_string_to_f64")]
+    pub cumulative_amount: f64,
+    #[serde(rename = "s", deserialize_with = "parse_order_status_code")]
+    pub status: OrderStatus,
+    #[serde(rename = "O")]
+    pub create_time: u128
+}
+
+impl WsOrderUpdate {
+    pub fn side(&self) -> OrderSide {
+        side_from_code(self.trade_type)
+    }
+}
+
+// https://mexcdevelop.github.io/apidocs/spot_v3_en/#spot-account-deals
+#[derive(Deserialize, Debug, Clone)]
+pub struct WsPrivateDeal {
+    #[serde(rename = "p", deserialize_with = "parse_string_to_f64")]
+    pub price: f64,
+    #[serde(rename = "v", deserialize_with = "parse_string_to_f64")]
+    pub quantity: f64,
+    #[serde(rename = "a", deserialize_with = "parse_string_to_f64")]
+    pub amount: f64,
+    /// 1 = buy, 2 = sell
+    #[serde(rename = "S")]
+    pub trade_type: u8,
+    #[serde(rename = "m", default, deserialize_with = "parse_bool")]
+    pub is_maker: bool,
+    #[serde(rename = "st", default, deserialize_with = "parse_bool")]
+    pub is_self_trade: bool,
+    #[serde(rename = "t")]
+    pub trade_id: String,
+    #[serde(rename = "c", default)]
+    pub client_order_id: String,
+    #[serde(rename = "i")]
+    pub order_id: String,
+    #[serde(rename = "n", deserialize_with = "parse_string_to_f64")]
+    pub fee: f64,
+    #[serde(rename = "N")]
+    pub fee_currency: String,
+    #[serde(rename = "T")]
+    pub time: u128
+}
+
+impl WsPrivateDeal {
+    pub fn side(&self) -> OrderSide {
+        side_from_code(self.trade_type)
+    }
+}
+
+/// A push on a subscribed channel
+#[derive(Debug, Clone)]
+pub struct SpotPush<T> {
+    pub channel: String,
+    pub symbol: String,
+    pub timestamp: u128,
+    pub data: T
+}
+
+#[derive(Debug, Clone)]
+pub enum SpotWsEvent {
+    Deals(SpotPush<Vec<WsDeal>>),
+    Kline(SpotPush<WsKline>),
+    IncreaseDepth(SpotPush<WsDepth>),
+    LimitDepth(SpotPush<WsDepth>),
+    BookTicker(SpotPush<WsBookTicker>),
+    AccountUpdate(SpotPush<WsAccountUpdate>),
+    OrderUpdate(SpotPush<WsOrderUpdate>),
+    PrivateDeal(SpotPush<WsPrivateDeal>),
+    /// Reply to a subscription request, `msg` echoes the topics or the rejection reason
+    Ack { id: i64, code: i64, msg: String },
+    /// The connection is (re)established and all subscriptions were sent again
+    Connected,
+    /// The connection dropped, a reconnect is pending
+    Disconnected
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct PushFrame {
+    pub c: String,
+    pub d: serde_json::Value,
+    #[serde(default)]
+    pub s: String,
+    #[serde(default)]
+    pub t: u128
+}
+
+#[derive(Deserialize, Debug)]
+pub(crate) struct AckFrame {
+    #[serde(default)]
+    pub id: i64,
+    pub code: i64,
+    pub msg: String
+}
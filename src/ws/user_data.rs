@@ -0,0 +1,301 @@
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex};
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use crate::{Mexc, MexcResult};
+use super::{default_url, MexcSpotWs, DEFAULT_EVENT_BUFFER, DEFAULT_PING_INTERVAL, DEFAULT_RECONNECT_DELAY};
+use super::structures::{SpotChannel, SpotWsEvent, WsFormat};
+
+// https://mexcdevelop.github.io/apidocs/spot_v3_en/#websocket-user-data-streams
+/// Listen keys expire 60 minutes after the last keep alive
+pub const LISTEN_KEY_KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(30 * 60);
+/// Connections are cut after 24 hours, the key is replaced a little before that
+pub const LISTEN_KEY_MAX_AGE: Duration = Duration::from_secs(23 * 60 * 60);
+/// Delay before retrying a failed keep alive or key creation
+pub const LISTEN_KEY_RETRY_DELAY: Duration = Duration::from_secs(30);
+
+pub fn listen_key_url(base_url: &str, listen_key: &str) -> String {
+    format!("{base_url}?listenKey={listen_key}")
+}
+
+pub struct UserDataStreamBuilder {
+    client: Arc<Mexc>,
+    url: Option<String>,
+    format: WsFormat,
+    channels: Vec<SpotChannel>,
+    keep_alive_interval: Duration,
+    max_age: Duration,
+    ping_interval: Duration,
+    reconnect_delay: Duration,
+    event_buffer: usize
+}
+
+impl UserDataStreamBuilder {
+
+    pub fn new(client: Arc<Mexc>) -> Self {
+        Self {
+            client,
+            url: None,
+            format: WsFormat::default(),
+            channels: vec![SpotChannel::Account, SpotChannel::Orders, SpotChannel::PrivateDeals],
+            keep_alive_interval: LISTEN_KEY_KEEP_ALIVE_INTERVAL,
+            max_age: LISTEN_KEY_MAX_AGE,
+            ping_interval: DEFAULT_PING_INTERVAL,
+            reconnect_delay: DEFAULT_RECONNECT_DELAY,
+            event_buffer: DEFAULT_EVENT_BUFFER
+        }
+    }
+
+    /// Endpoint without the listen key, defaults to the one matching the format
+    pub fn url(mut self, url: impl Into<String>) -> Self {
+        self.url = Some(url.into());
+        self
+    }
+
+    pub fn format(mut self, format: WsFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Private channels to subscribe, all three by default
+    pub fn channels(mut self, channels: Vec<SpotChannel>) -> Self {
+        self.channels = channels;
+        self
+    }
+
+    pub fn keep_alive_interval(mut self, keep_alive_interval: Duration) -> Self {
+        self.keep_alive_interval = keep_alive_interval;
+        self
+    }
+
+    /// Age after which the listen key is replaced by a new one and the connection moved over
+    pub fn max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = max_age;
+        self
+    }
+
+    pub fn ping_interval(mut self, ping_interval: Duration) -> Self {
+        self.ping_interval = ping_interval;
+        self
+    }
+
+    pub fn reconnect_delay(mut self, reconnect_delay: Duration) -> Self {
+        self.reconnect_delay = reconnect_delay;
+        self
+    }
+
+    pub fn event_buffer(mut self, event_buffer: usize) -> Self {
+        self.event_buffer = event_buffer;
+        self
+    }
+
+    /// Creates a listen key, opens the connection and spawns the task keeping the key alive
+    pub async fn connect(self) -> MexcResult<(UserDataStream, mpsc::Receiver<SpotWsEvent>)> {
+        let base_url = self.url.unwrap_or_else(|| default_url(self.format).to_string());
+        let listen_key = self.client.get_listen_key().await?;
+
+        let connected = MexcSpotWs::builder()
+            .url(listen_key_url(&base_url, &listen_key))
+            .format(self.format)
+            .ping_interval(self.ping_interval)
+            .reconnect_delay(self.reconnect_delay)
+            .event_buffer(self.event_buffer)
+            .connect().await
+            .and_then(|(ws, events)| ws.subscribe(&self.channels).map(|_| (ws, events)));
+
+        let (ws, events) = match connected {
+            Ok(connected) => connected,
+            Err(err) => {
+                let _ = self.client.delete_listen_key(&listen_key).await;
+                return Err(err);
+            }
+        };
+
+        let listen_key = Arc::new(Mutex::new(listen_key));
+        let keeper = KeyKeeper {
+            client: self.client.clone(),
+            ws: ws.clone(),
+            base_url,
+            listen_key: listen_key.clone(),
+            keep_alive_interval: self.keep_alive_interval,
+            max_age: self.max_age
+        };
+        let keeper = tokio::spawn(keeper.run());
+
+        Ok((UserDataStream { client: self.client, ws, listen_key, keeper }, events))
+    }
+}
+
+/// Private account, order and deal pushes over a listen key that is kept alive and rotated in the
+/// background. A rotation reconnects, so `Disconnected` and `Connected` events bracket it.
+pub struct UserDataStream {
+    client: Arc<Mexc>,
+    ws: MexcSpotWs,
+    listen_key: Arc<Mutex<String>>,
+    keeper: JoinHandle<()>
+}
+
+impl UserDataStream {
+
+    pub fn builder(client: Arc<Mexc>) -> UserDataStreamBuilder {
+        UserDataStreamBuilder::new(client)
+    }
+
+    pub async fn connect(client: Arc<Mexc>) -> MexcResult<(UserDataStream, mpsc::Receiver<SpotWsEvent>)> {
+        Self::builder(client).connect().await
+    }
+
+    pub async fn listen_key(&self) -> String {
+        self.listen_key.lock().await.clone()
+    }
+
+    /// Handle to the underlying connection, e.g. to add market data topics
+    pub fn ws(&self) -> &MexcSpotWs {
+        &self.ws
+    }
+
+    /// Stops the keep alive task, closes the connection and deletes the listen key
+    pub async fn close(&self) -> MexcResult<String> {
+        // holding the key waits out a refresh in flight
+        let listen_key = self.listen_key.lock().await;
+        self.keeper.abort();
+        let _ = self.ws.close();
+        self.client.delete_listen_key(&listen_key).await
+    }
+}
+
+impl Drop for UserDataStream {
+    fn drop(&mut self) {
+        self.keeper.abort();
+    }
+}
+
+struct KeyKeeper {
+    client: Arc<Mexc>,
+    ws: MexcSpotWs,
+    base_url: String,
+    listen_key: Arc<Mutex<String>>,
+    keep_alive_interval: Duration,
+    max_age: Duration
+}
+
+impl KeyKeeper {
+
+    async fn run(self) {
+        let mut created = Instant::now();
+        let mut delay = self.keep_alive_interval;
+
+        loop {
+            tokio::time::sleep(delay).await;
+
+            let mut listen_key = self.listen_key.lock().await;
+            if self.ws.is_closed() {
+                let _ = self.client.delete_listen_key(&listen_key).await;
+                return;
+            }
+
+            let refreshed = created.elapsed() < self.max_age
+                && self.client.keep_alive_listen_key(&listen_key).await.is_ok();
+            if refreshed {
+                delay = self.keep_alive_interval;
+                continue;
+            }
+
+            // expired, rejected or too old: move the connection to a fresh key
+            let new_key = match self.client.get_listen_key().await {
+                Ok(new_key) => new_key,
+                Err(_) => {
+                    delay = LISTEN_KEY_RETRY_DELAY.min(self.keep_alive_interval);
+                    continue;
+                }
+            };
+
+            if self.ws.reconnect(Some(listen_key_url(&self.base_url, &new_key))).is_err() {
+                let _ = self.client.delete_listen_key(&new_key).await;
+                return;
+            }
+
+            let old_key = std::mem::replace(&mut *listen_key, new_key);
+            let _ = self.client.delete_listen_key(&old_key).await;
+            created = Instant::now();
+            delay = self.keep_alive_interval;
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::{SinkExt, StreamExt};
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::Message;
+    use tokio_tungstenite::tungstenite::handshake::server::{Request, Response};
+    use crate::testing::serve;
+
+    #[tokio::test]
+    #[allow(clippy::result_large_err)]
+    pub async fn test_rotates_listen_key() {
+        let (base_url, http) = serve(vec![
+            (200, r#"{"listenKey":"key1"}"#),
+            (200, r#"{"listenKey":"key2"}"#),
+            (200, r#"{"listenKey":"key1"}"#),
+            (200, r#"{"listenKey":"key2"}"#)
+        ]).await;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let ws_url = format!("ws://{}/ws", listener.local_addr().unwrap());
+
+        let server = tokio::spawn(async move {
+            let mut paths = vec![];
+            let mut sockets = vec![];
+            for _ in 0..2 {
+                let (socket, _) = listener.accept().await.unwrap();
+                let mut path = String::new();
+                let mut ws = tokio_tungstenite::accept_hdr_async(socket, |req: &Request, resp: Response| {
+                    path = req.uri().to_string();
+                    Ok(resp)
+                }).await.unwrap();
+                paths.push(path);
+
+                let Some(Ok(Message::Text(subscription))) = ws.next().await else { panic!("expected subscription") };
+                assert!(subscription.contains("spot@private.orders.v3.api"));
+                sockets.push(ws);
+            }
+
+            let push = r#"{"c":"spot@private.orders.v3.api","d":{"A":8.0,"O":1661938138000,"S":1,"V":10,"a":8,"c":"","i":"e03a5c7441e44ed899466a7140b71391","m":0,"o":1,"p":0.8,"s":1,"v":10,"ap":0,"cv":0,"ca":0},"s":"MXUSDT","t":1661938138193}"#;
+            sockets[1].send(Message::Text(push.into())).await.unwrap();
+            let _ = sockets[1].next().await;
+            paths
+        });
+
+        let client = Mexc::builder().base_url(base_url).api_key("key").api_secret("secret").build().unwrap();
+        let (stream, mut events) = UserDataStream::builder(Arc::new(client))
+            .url(ws_url)
+            .format(WsFormat::Json)
+            .keep_alive_interval(Duration::from_millis(200))
+            .max_age(Duration::ZERO)
+            .reconnect_delay(Duration::from_millis(10))
+            .connect().await.unwrap();
+
+        assert!(matches!(events.recv().await, Some(SpotWsEvent::Connected)));
+        assert!(matches!(events.recv().await, Some(SpotWsEvent::Disconnected)));
+        assert!(matches!(events.recv().await, Some(SpotWsEvent::Connected)));
+        let Some(SpotWsEvent::OrderUpdate(push)) = events.recv().await else { panic!("expected order update") };
+        assert_eq!(push.symbol, "MXUSDT");
+        assert_eq!(push.data.order_id, "e03a5c7441e44ed899466a7140b71391");
+        assert_eq!(push.data.price, 0.8);
+        assert!(!push.data.is_maker);
+
+        assert_eq!(stream.listen_key().await, "key2");
+        assert_eq!(stream.close().await.unwrap(), "key2");
+
+        assert_eq!(server.await.unwrap(), vec!["/ws?listenKey=key1", "/ws?listenKey=key2"]);
+        let requests = http.await.unwrap();
+        assert!(requests[0].starts_with("POST /api/v3/userDataStream"));
+        assert!(requests[1].starts_with("POST /api/v3/userDataStream"));
+        assert!(requests[2].starts_with("DELETE /api/v3/userDataStream?listenKey=key1"));
+        assert!(requests[3].starts_with("DELETE /api/v3/userDataStream?listenKey=key2"));
+    }
+}